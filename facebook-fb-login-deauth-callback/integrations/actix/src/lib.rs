@@ -0,0 +1,149 @@
+pub use facebook_fb_login_deauth_callback;
+pub use facebook_fb_login_deauth_callback::context::{
+    async_trait, CachedContext, Context, ContextError, ParseOptions, Secret,
+};
+
+use std::collections::HashMap;
+
+use actix_web::{
+    web::{self, Data, Form, FormConfig, Path},
+    HttpResponse, Scope,
+};
+use facebook_fb_login_deauth_callback::{
+    get::PASS_BACK_STATUS_CODE,
+    post::{
+        pass_back_with_signed_request_and_options, PassBackCallbackFn, SIGNED_REQUEST_FORM_KEY,
+    },
+};
+
+pub fn scope<C: Context + 'static>(
+    path_prefix: &str,
+    ctx: C,
+    callback: PassBackCallbackFn<'static, C>,
+) -> Scope {
+    web::scope(&format!("/{}", path_prefix))
+        .app_data(Data::new(ctx))
+        .app_data(Data::new(callback))
+        .app_data(FormConfig::default().limit(1024 * 32))
+        .route("/{app_id}", web::get().to(get_handler::<C>))
+        .route("/{app_id}", web::post().to(post_handler::<C>))
+}
+
+async fn get_handler<C: Context + 'static>(ctx: Data<C>, app_id: Path<u64>) -> HttpResponse {
+    match ctx.get_app_secret(app_id.into_inner()).await {
+        Ok(_) => HttpResponse::build(PASS_BACK_STATUS_CODE).finish(),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+async fn post_handler<C: Context + 'static>(
+    ctx: Data<C>,
+    callback: Data<PassBackCallbackFn<'static, C>>,
+    app_id: Path<u64>,
+    request_form: Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let app_id = app_id.into_inner();
+
+    match request_form.get(SIGNED_REQUEST_FORM_KEY) {
+        Some(signed_request) => match ctx.get_app_secrets(app_id).await {
+            Ok(app_secrets) => {
+                let res = pass_back_with_signed_request_and_options(
+                    signed_request,
+                    &app_secrets,
+                    &ctx.get_parse_options(app_id),
+                    ctx.get_ref().clone(),
+                    callback.into_inner(),
+                )
+                .await;
+                if let Some(index) = res.app_secret_index {
+                    ctx.on_app_secret_matched(app_id, index);
+                }
+
+                HttpResponse::build(res.status_code).body(res.body)
+            }
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        },
+        None => HttpResponse::BadRequest().body("form invalid"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::time::Duration;
+
+    use actix_web::{http::StatusCode, test, App};
+    use chrono::{TimeZone as _, Utc};
+    use facebook_signed_request::fb_login_deauth_callback::{encode, Payload};
+
+    #[derive(Debug, Clone)]
+    struct MyContext;
+
+    #[async_trait]
+    impl Context for MyContext {
+        async fn get_app_secret(&self, _app_id: u64) -> Result<Secret, ContextError> {
+            Ok(Secret::new("key"))
+        }
+
+        fn get_parse_options(&self, app_id: u64) -> ParseOptions {
+            match app_id {
+                2 => ParseOptions::default().with_max_age(Duration::from_secs(60)),
+                _ => ParseOptions::default(),
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_scope() {
+        let app = test::init_service(App::new().service(scope(
+            "fb_deauth_callback",
+            MyContext,
+            Box::new(|payload, _ctx| {
+                Box::pin(async move {
+                    assert_eq!(payload.user_id, 1);
+                    Ok(())
+                })
+            }),
+        )))
+        .await;
+
+        //
+        let req = test::TestRequest::get()
+            .uri("/fb_deauth_callback/1")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), PASS_BACK_STATUS_CODE);
+
+        //
+        let signed_request = encode(
+            &Payload::new(1, Utc.timestamp_opt(1624244156, 0).unwrap()),
+            "key",
+        )
+        .unwrap();
+        let post = |app_id: u64, form: &[(&str, &str)]| {
+            test::TestRequest::post()
+                .uri(&format!("/fb_deauth_callback/{app_id}"))
+                .set_form(form)
+                .to_request()
+        };
+
+        let res = test::call_service(
+            &app,
+            post(1, &[(SIGNED_REQUEST_FORM_KEY, signed_request.as_str())]),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = test::call_service(&app, post(1, &[("foo", "bar")])).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = test::call_service(
+            &app,
+            post(2, &[(SIGNED_REQUEST_FORM_KEY, signed_request.as_str())]),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(test::read_body(res).await, "Expired");
+    }
+}
@@ -8,9 +8,11 @@ sudo systemctl reload nginx
 cargo run -p facebook-fb-login-deauth-callback-warp-demo -- 202000000000000 YOUR_APP_SECRET
 */
 
-use std::env;
+use std::{env, time::Duration};
 
-use facebook_fb_login_deauth_callback_warp::Context;
+use facebook_fb_login_deauth_callback_warp::{
+    async_trait, CachedContext, Context, ContextError, Secret,
+};
 use warp::Filter as _;
 
 #[tokio::main]
@@ -46,17 +48,17 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
         app_id, path_prefix, listen_port
     );
 
-    let ctx = MyContext {
+    let ctx = CachedContext::new(MyContext {
         app_id,
         app_secret,
         db: 1,
-    };
+    });
     let api = facebook_fb_login_deauth_callback_warp::handle(
         path_prefix,
         ctx,
         Box::new(move |payload, ctx| {
             Box::pin(async move {
-                let _ = ctx.db;
+                let _ = ctx.inner().db;
 
                 println!("payload: {:?}", payload);
 
@@ -79,13 +81,11 @@ struct MyContext {
 
     db: i64,
 }
+#[async_trait]
 impl Context for MyContext {
-    fn get_app_secret(
-        &self,
-        app_id: u64,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    async fn get_app_secret(&self, app_id: u64) -> Result<Secret, ContextError> {
         if app_id == self.app_id {
-            Ok(self.app_secret.to_owned())
+            Ok(Secret::new(self.app_secret.to_owned()).with_ttl(Duration::from_secs(300)))
         } else {
             Err("app_id mismatch".into())
         }
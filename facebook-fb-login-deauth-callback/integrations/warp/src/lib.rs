@@ -1,11 +1,16 @@
 pub use facebook_fb_login_deauth_callback;
+pub use facebook_fb_login_deauth_callback::context::{
+    async_trait, CachedContext, Context, ContextError, ParseOptions, Secret,
+};
 
 use core::convert::Infallible;
 use std::{collections::HashMap, sync::Arc};
 
 use facebook_fb_login_deauth_callback::{
     get::PASS_BACK_STATUS_CODE,
-    post::{pass_back_with_signed_request, PassBackCallbackFn, SIGNED_REQUEST_FORM_KEY},
+    post::{
+        pass_back_with_signed_request_and_options, PassBackCallbackFn, SIGNED_REQUEST_FORM_KEY,
+    },
 };
 use warp::{
     http::{Response, StatusCode},
@@ -13,14 +18,7 @@ use warp::{
     Filter,
 };
 
-pub trait Context: Send + Sync + Clone {
-    fn get_app_secret(
-        &self,
-        app_id: u64,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>>;
-}
-
-pub fn handle<C: Context>(
+pub fn handle<C: Context + 'static>(
     path_prefix: String,
     ctx: C,
     callback: PassBackCallbackFn<'static, C>,
@@ -32,7 +30,7 @@ pub fn handle<C: Context>(
     ))
 }
 
-fn get_filter<C: Context>(
+fn get_filter<C: Context + 'static>(
     path_prefix: String,
     ctx: C,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
@@ -45,7 +43,7 @@ fn get_filter<C: Context>(
 
             async move {
                 let part: Result<Result<Response<Body>, warp::http::Error>, Infallible> = {
-                    match ctx.get_app_secret(app_id) {
+                    match ctx.get_app_secret(app_id).await {
                         Ok(_) => Ok(Response::builder()
                             .status(PASS_BACK_STATUS_CODE)
                             .body("".into())),
@@ -59,7 +57,7 @@ fn get_filter<C: Context>(
         })
 }
 
-fn post_filter<C: Context>(
+fn post_filter<C: Context + 'static>(
     path_prefix: String,
     ctx: C,
     callback: Arc<PassBackCallbackFn<'static, C>>,
@@ -76,15 +74,19 @@ fn post_filter<C: Context>(
             async move {
                 let part: Result<Result<Response<Body>, warp::http::Error>, Infallible> = {
                     match request_form.get(SIGNED_REQUEST_FORM_KEY) {
-                        Some(signed_request) => match ctx.get_app_secret(app_id) {
-                            Ok(app_secret) => {
-                                let res = pass_back_with_signed_request(
+                        Some(signed_request) => match ctx.get_app_secrets(app_id).await {
+                            Ok(app_secrets) => {
+                                let res = pass_back_with_signed_request_and_options(
                                     signed_request,
-                                    &app_secret,
-                                    ctx,
+                                    &app_secrets,
+                                    &ctx.get_parse_options(app_id),
+                                    ctx.clone(),
                                     callback,
                                 )
                                 .await;
+                                if let Some(index) = res.app_secret_index {
+                                    ctx.on_app_secret_matched(app_id, index);
+                                }
 
                                 Ok(Response::builder()
                                     .status(res.status_code)
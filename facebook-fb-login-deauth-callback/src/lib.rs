@@ -1,4 +1,5 @@
 //! [Official doc](https://developers.facebook.com/docs/facebook-login/manually-build-a-login-flow/#deauth-callback)
 
+pub mod context;
 pub mod get;
 pub mod post;
@@ -2,8 +2,10 @@ use core::{future::Future, pin::Pin};
 use std::sync::Arc;
 
 use facebook_signed_request::{
-    fb_login_deauth_callback::{parse as signed_request_parse, Payload as SignedRequestPayload},
-    ParseError as SignedRequestParseError,
+    fb_login_deauth_callback::{
+        parse_with_options as signed_request_parse_with_options, Payload as SignedRequestPayload,
+    },
+    ParseError as SignedRequestParseError, ParseOptions as SignedRequestParseOptions,
 };
 use http::StatusCode;
 
@@ -24,14 +26,31 @@ pub async fn pass_back<C>(
     app_secret: &str,
     ctx: C,
     callback: Arc<PassBackCallbackFn<'_, C>>,
+) -> PassBackResponse {
+    pass_back_with_secrets(request_body_bytes, &[app_secret], ctx, callback).await
+}
+
+/// Same as [`pass_back`], [`PassBackResponse::app_secret_index`] is the index of the matched app secret.
+pub async fn pass_back_with_secrets<C, S: AsRef<str>>(
+    request_body_bytes: &[u8],
+    app_secrets: &[S],
+    ctx: C,
+    callback: Arc<PassBackCallbackFn<'_, C>>,
 ) -> PassBackResponse {
     match form_urlencoded::parse(request_body_bytes).find(|(k, _)| k == SIGNED_REQUEST_FORM_KEY) {
         Some((_, signed_request)) => {
-            pass_back_with_signed_request(signed_request.as_ref(), app_secret, ctx, callback).await
+            pass_back_with_signed_request_and_secrets(
+                signed_request.as_ref(),
+                app_secrets,
+                ctx,
+                callback,
+            )
+            .await
         }
         None => PassBackResponse {
             status_code: StatusCode::BAD_REQUEST,
             body: "".to_owned(),
+            app_secret_index: None,
         },
     }
 }
@@ -42,15 +61,45 @@ pub async fn pass_back_with_signed_request<C>(
     ctx: C,
     callback: Arc<PassBackCallbackFn<'_, C>>,
 ) -> PassBackResponse {
-    match signed_request_parse(signed_request, app_secret) {
-        Ok(payload) => match callback(payload, ctx).await {
+    pass_back_with_signed_request_and_secrets(signed_request, &[app_secret], ctx, callback).await
+}
+
+/// Same as [`pass_back_with_signed_request`], [`PassBackResponse::app_secret_index`] is the index of the matched app secret.
+pub async fn pass_back_with_signed_request_and_secrets<C, S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+    ctx: C,
+    callback: Arc<PassBackCallbackFn<'_, C>>,
+) -> PassBackResponse {
+    pass_back_with_signed_request_and_options(
+        signed_request,
+        app_secrets,
+        &SignedRequestParseOptions::default(),
+        ctx,
+        callback,
+    )
+    .await
+}
+
+/// Same as [`pass_back_with_signed_request_and_secrets`], `400` when the signed request is expired or issued in the future.
+pub async fn pass_back_with_signed_request_and_options<C, S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+    options: &SignedRequestParseOptions,
+    ctx: C,
+    callback: Arc<PassBackCallbackFn<'_, C>>,
+) -> PassBackResponse {
+    match signed_request_parse_with_options(signed_request, app_secrets, options) {
+        Ok((payload, app_secret_index)) => match callback(payload, ctx).await {
             Ok(_) => PassBackResponse {
                 status_code: StatusCode::OK,
                 body: "".to_owned(),
+                app_secret_index: Some(app_secret_index),
             },
             Err(err) => PassBackResponse {
                 status_code: StatusCode::INTERNAL_SERVER_ERROR,
                 body: err.to_string(),
+                app_secret_index: Some(app_secret_index),
             },
         },
         Err(err) => match err {
@@ -58,13 +107,17 @@ pub async fn pass_back_with_signed_request<C>(
             | SignedRequestParseError::PayloadMissing
             | SignedRequestParseError::SignedRequestInvalid
             | SignedRequestParseError::EncodedSignatureBase64DecodeFailed(_)
-            | SignedRequestParseError::PayloadBase64DecodeFailed(_) => PassBackResponse {
+            | SignedRequestParseError::PayloadBase64DecodeFailed(_)
+            | SignedRequestParseError::Expired
+            | SignedRequestParseError::IssuedInFuture => PassBackResponse {
                 status_code: StatusCode::BAD_REQUEST,
                 body: err.to_string(),
+                app_secret_index: None,
             },
             _ => PassBackResponse {
                 status_code: StatusCode::INTERNAL_SERVER_ERROR,
                 body: err.to_string(),
+                app_secret_index: None,
             },
         },
     }
@@ -74,4 +127,63 @@ pub async fn pass_back_with_signed_request<C>(
 pub struct PassBackResponse {
     pub status_code: StatusCode,
     pub body: String,
+    /// Index of the app secret the signed request matched, `None` when it isn't verified.
+    pub app_secret_index: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::time::Duration;
+
+    use chrono::{TimeZone as _, Utc};
+    use facebook_signed_request::fb_login_deauth_callback::encode as signed_request_encode;
+
+    #[tokio::test]
+    async fn test_pass_back() {
+        let signed_request = signed_request_encode(
+            &SignedRequestPayload::new(1, Utc.timestamp_opt(1624244156, 0).unwrap()),
+            "key",
+        )
+        .unwrap();
+        let request_body = form_urlencoded::Serializer::new(String::new())
+            .append_pair(SIGNED_REQUEST_FORM_KEY, &signed_request)
+            .finish();
+
+        let callback: Arc<PassBackCallbackFn<'_, ()>> = Arc::new(Box::new(|payload, _ctx| {
+            Box::pin(async move {
+                assert_eq!(payload.user_id, 1);
+                Ok(())
+            })
+        }));
+
+        let res = pass_back_with_secrets(
+            request_body.as_bytes(),
+            &["other", "key"],
+            (),
+            callback.clone(),
+        )
+        .await;
+        assert_eq!(res.status_code, StatusCode::OK);
+        assert_eq!(res.app_secret_index, Some(1));
+
+        let res = pass_back(request_body.as_bytes(), "other", (), callback.clone()).await;
+        assert_eq!(res.status_code, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(res.body, "SignatureMismatch");
+
+        let res = pass_back(b"foo=bar", "key", (), callback.clone()).await;
+        assert_eq!(res.status_code, StatusCode::BAD_REQUEST);
+
+        let res = pass_back_with_signed_request_and_options(
+            &signed_request,
+            &["key"],
+            &SignedRequestParseOptions::default().with_max_age(Duration::from_secs(60)),
+            (),
+            callback,
+        )
+        .await;
+        assert_eq!(res.status_code, StatusCode::BAD_REQUEST);
+        assert_eq!(res.body, "Expired");
+    }
 }
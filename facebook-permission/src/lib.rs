@@ -55,6 +55,8 @@ pub enum FacebookPermission {
     #[serde(other)]
     Other(String),
 }
+// Not derived, the variants above are regenerated by html_parser.
+#[allow(clippy::derivable_impls)]
 impl Default for FacebookPermission {
     fn default() -> Self {
         Self::Email
@@ -62,18 +64,16 @@ impl Default for FacebookPermission {
 }
 
 /// [Official doc](https://developers.facebook.com/docs/graph-api/reference/user/permissions/#parameters)
-#[derive(Deserialize_enum_str, Serialize_enum_str, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(
+    Deserialize_enum_str, Serialize_enum_str, Debug, Clone, Copy, PartialEq, Eq, Hash, Default,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum FacebookPermissionStatus {
+    #[default]
     Granted,
     Declined,
     Expired,
 }
-impl Default for FacebookPermissionStatus {
-    fn default() -> Self {
-        Self::Granted
-    }
-}
 
 #[cfg(test)]
 mod tests {
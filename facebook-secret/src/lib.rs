@@ -0,0 +1,255 @@
+//! Per app id secrets, and their cache, shared by the `Context` traits of the framework integrations.
+
+use core::{future::Future, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+pub use async_trait::async_trait;
+
+use tokio::sync::OnceCell;
+
+pub type ContextError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret {
+    pub value: String,
+    /// How long [`CachedContext`] can keep it, `None` means don't cache.
+    pub ttl: Option<Duration>,
+}
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            ttl: None,
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+}
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+impl AsRef<str> for Secret {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+impl core::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Secret")
+            .field("value", &"***")
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecretKind {
+    VerifyToken,
+    AppSecret,
+    AppSecrets,
+}
+
+/// Cache the secrets of the inner `Context` until their `ttl` elapsed.
+///
+/// The `Context` traits are implemented for it by the crates that define them, with [`CachedContext::get_or_load`].
+#[derive(Clone)]
+pub struct CachedContext<C> {
+    inner: C,
+    cache: Arc<Mutex<HashMap<CacheKey, CacheValue>>>,
+    /// The loads in flight, the concurrent misses of the same key wait for the first one.
+    loading: Arc<Mutex<HashMap<CacheKey, Arc<Loading>>>>,
+}
+type CacheKey = (SecretKind, u64);
+type CacheValue = (Vec<Secret>, Instant);
+/// The error isn't Clone, the waiters get its message.
+type Loading = OnceCell<Result<Vec<Secret>, String>>;
+
+impl<C: core::fmt::Debug> core::fmt::Debug for CachedContext<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CachedContext")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+impl<C> CachedContext<C> {
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            cache: Default::default(),
+            loading: Default::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn invalidate(&self, app_id: u64) {
+        self.cache
+            .lock()
+            .expect("cache lock poisoned")
+            .retain(|(_, x), _| *x != app_id);
+    }
+
+    /// The cached secrets, otherwise `load` them, once for all the concurrent callers.
+    pub async fn get_or_load<F, Fut>(
+        &self,
+        kind: SecretKind,
+        app_id: u64,
+        load: F,
+    ) -> Result<Vec<Secret>, ContextError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Vec<Secret>, ContextError>>,
+    {
+        if let Some(secrets) = self.get_cached(kind, app_id) {
+            return Ok(secrets);
+        }
+
+        let loading = self
+            .loading
+            .lock()
+            .expect("loading lock poisoned")
+            .entry((kind, app_id))
+            .or_default()
+            .clone();
+        let ret = loading
+            .get_or_init(|| async {
+                match load().await {
+                    Ok(secrets) => {
+                        self.set_cached(kind, app_id, &secrets);
+                        Ok(secrets)
+                    }
+                    Err(err) => Err(err.to_string()),
+                }
+            })
+            .await
+            .to_owned();
+
+        // The next miss loads again, e.g. after an error or when it isn't cacheable.
+        let mut loading_map = self.loading.lock().expect("loading lock poisoned");
+        if matches!(loading_map.get(&(kind, app_id)), Some(x) if Arc::ptr_eq(x, &loading)) {
+            loading_map.remove(&(kind, app_id));
+        }
+        drop(loading_map);
+
+        ret.map_err(Into::into)
+    }
+
+    fn get_cached(&self, kind: SecretKind, app_id: u64) -> Option<Vec<Secret>> {
+        let mut cache = self.cache.lock().expect("cache lock poisoned");
+        match cache.get(&(kind, app_id)) {
+            Some((secrets, expires_at)) if *expires_at > Instant::now() => Some(secrets.to_owned()),
+            Some(_) => {
+                cache.remove(&(kind, app_id));
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Kept for the shortest `ttl`, not cached when any of them is `None`.
+    ///
+    /// The expired ones of the other apps are evicted too, they may never be accessed again.
+    fn set_cached(&self, kind: SecretKind, app_id: u64, secrets: &[Secret]) {
+        let ttl = secrets
+            .iter()
+            .map(|x| x.ttl)
+            .min_by_key(|x| x.unwrap_or_default());
+        if let Some(Some(ttl)) = ttl {
+            let now = Instant::now();
+            let mut cache = self.cache.lock().expect("cache lock poisoned");
+            cache.retain(|_, (_, expires_at)| *expires_at > now);
+            cache.insert((kind, app_id), (secrets.to_owned(), now + ttl));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_secret_debug() {
+        let secret = Secret::new("secret").with_ttl(Duration::from_secs(60));
+        assert_eq!(
+            format!("{secret:?}"),
+            r#"Secret { value: "***", ttl: Some(60s) }"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_or_load() {
+        let ctx = CachedContext::new(());
+        let calls = AtomicUsize::new(0);
+        let load = |secret: Secret| {
+            let calls = &calls;
+            move || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+                match secret.value.as_str() {
+                    "" => Err("empty".into()),
+                    _ => Ok(vec![secret]),
+                }
+            }
+        };
+        let secret = Secret::new("secret").with_ttl(Duration::from_secs(60));
+
+        // single flight
+        let (a, b) = tokio::join!(
+            ctx.get_or_load(SecretKind::AppSecret, 1, load(secret.to_owned())),
+            ctx.get_or_load(SecretKind::AppSecret, 1, load(secret.to_owned())),
+        );
+        assert_eq!(a.unwrap(), vec![secret.to_owned()]);
+        assert_eq!(b.unwrap(), vec![secret.to_owned()]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (a, b) = tokio::join!(
+            ctx.get_or_load(SecretKind::AppSecret, 2, load(Secret::new(""))),
+            ctx.get_or_load(SecretKind::AppSecret, 2, load(Secret::new(""))),
+        );
+        assert_eq!(a.unwrap_err().to_string(), "empty");
+        assert_eq!(b.unwrap_err().to_string(), "empty");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(ctx.loading.lock().unwrap().is_empty());
+
+        // cached
+        ctx.get_or_load(SecretKind::AppSecret, 1, load(secret.to_owned()))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // eviction
+        let short = Secret::new("short").with_ttl(Duration::from_millis(10));
+        ctx.get_or_load(SecretKind::AppSecret, 3, load(short))
+            .await
+            .unwrap();
+        assert_eq!(ctx.cache.lock().unwrap().len(), 2);
+        std::thread::sleep(Duration::from_millis(20));
+        ctx.get_or_load(SecretKind::AppSecret, 4, load(secret.to_owned()))
+            .await
+            .unwrap();
+        assert_eq!(ctx.cache.lock().unwrap().len(), 2);
+        assert!(!ctx
+            .cache
+            .lock()
+            .unwrap()
+            .contains_key(&(SecretKind::AppSecret, 3)));
+    }
+}
@@ -1,14 +1,19 @@
 //! [Official doc](https://developers.facebook.com/docs/development/create-an-app/app-dashboard/data-deletion-callback)
 
 use chrono::{serde::ts_seconds, DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_aux::field_attributes::deserialize_number_from_string;
 
-use crate::ParseError;
+use crate::{
+    serde_ext::serialize_to_string, EncodeError, ParseError, ParseOptions, NORMALLY_ALGORITHM,
+};
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Payload {
-    #[serde(deserialize_with = "deserialize_number_from_string")]
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
     pub user_id: u64,
     pub algorithm: String,
     #[serde(with = "ts_seconds")]
@@ -16,16 +21,51 @@ pub struct Payload {
     #[serde(with = "ts_seconds")]
     pub expires: DateTime<Utc>,
 }
+impl Payload {
+    pub fn new(user_id: u64, issued_at: DateTime<Utc>, expires: DateTime<Utc>) -> Self {
+        Self {
+            user_id,
+            algorithm: NORMALLY_ALGORITHM.to_owned(),
+            issued_at,
+            expires,
+        }
+    }
+}
 impl crate::Payload for Payload {
     fn algorithm(&self) -> Option<&str> {
         Some(&self.algorithm)
     }
+    fn issued_at(&self) -> Option<i64> {
+        Some(self.issued_at.timestamp())
+    }
+    fn expires(&self) -> Option<i64> {
+        Some(self.expires.timestamp())
+    }
 }
 
 pub fn parse(signed_request: &str, app_secret: &str) -> Result<Payload, ParseError> {
     crate::parse(signed_request, app_secret)
 }
 
+pub fn parse_with_secrets<S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+) -> Result<(Payload, usize), ParseError> {
+    crate::parse_with_secrets(signed_request, app_secrets)
+}
+
+pub fn parse_with_options<S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+    options: &ParseOptions,
+) -> Result<(Payload, usize), ParseError> {
+    crate::parse_with_options(signed_request, app_secrets, options)
+}
+
+pub fn encode(payload: &Payload, app_secret: &str) -> Result<String, EncodeError> {
+    crate::encode(payload, app_secret, &payload.algorithm)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,4 +84,39 @@ mod tests {
             Err(err) => panic!("{}", err),
         }
     }
+
+    #[test]
+    fn test_parse_with_options() {
+        use core::time::Duration;
+        use std::time::UNIX_EPOCH;
+
+        use chrono::TimeZone as _;
+
+        let signed_request = encode(
+            &Payload::new(
+                218471,
+                Utc.timestamp_opt(1291836800, 0).unwrap(),
+                Utc.timestamp_opt(1291840400, 0).unwrap(),
+            ),
+            "key",
+        )
+        .unwrap();
+        let options = |secs: u64| {
+            ParseOptions::default()
+                .with_clock_skew(Duration::ZERO)
+                .with_clock(move || UNIX_EPOCH + Duration::from_secs(secs))
+        };
+
+        assert_eq!(
+            parse_with_options(&signed_request, &["key"], &options(1291840400))
+                .unwrap()
+                .0
+                .user_id,
+            218471
+        );
+        match parse_with_options(&signed_request, &["key"], &options(1291840401)) {
+            Err(ParseError::Expired) => {}
+            x => panic!("{:?}", x),
+        }
+    }
 }
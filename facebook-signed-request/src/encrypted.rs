@@ -0,0 +1,210 @@
+//! Legacy encrypted signed request, `{"algorithm":"AES-256-CBC HMAC-SHA256","issued_at":..,"iv":..,"payload":..}`.
+//!
+//! The envelope is signed like [`crate::parse`], `payload` is the JSON encrypted with AES-256-CBC and PKCS#7 padding,
+//! the key is the app secret, which is 32 characters.
+
+use aes::{
+    cipher::{block_padding::Pkcs7, BlockDecryptMut as _, BlockEncryptMut as _, KeyIvInit as _},
+    Aes256,
+};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{EncodeError, ParseError, ParseOptions};
+
+type Aes256CbcDec = cbc::Decryptor<Aes256>;
+type Aes256CbcEnc = cbc::Encryptor<Aes256>;
+
+pub const ENCRYPTED_ALGORITHM: &str = "AES-256-CBC HMAC-SHA256";
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Envelope {
+    pub algorithm: String,
+    /// Unix timestamp in seconds.
+    pub issued_at: i64,
+    pub iv: String,
+    pub payload: String,
+}
+impl crate::Payload for Envelope {
+    fn algorithm(&self) -> Option<&str> {
+        Some(&self.algorithm)
+    }
+    fn issued_at(&self) -> Option<i64> {
+        Some(self.issued_at)
+    }
+}
+
+pub fn parse<T: DeserializeOwned>(signed_request: &str, app_secret: &str) -> Result<T, ParseError> {
+    parse_with_secrets(signed_request, &[app_secret]).map(|(data, _)| data)
+}
+
+/// Same as [`parse`], also returns the index of the first matched app secret, the payload is decrypted with it.
+pub fn parse_with_secrets<T: DeserializeOwned, S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+) -> Result<(T, usize), ParseError> {
+    parse_envelope(signed_request, app_secrets)
+        .and_then(|(envelope, i)| decrypt(&envelope, app_secrets[i].as_ref()).map(|x| (x, i)))
+}
+
+/// Same as [`parse_with_secrets`], then reject the stale envelope.
+pub fn parse_with_options<T: DeserializeOwned, S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+    options: &ParseOptions,
+) -> Result<(T, usize), ParseError> {
+    let (envelope, i) = parse_envelope(signed_request, app_secrets)?;
+    options.check(&envelope)?;
+    decrypt(&envelope, app_secrets[i].as_ref()).map(|x| (x, i))
+}
+
+fn parse_envelope<S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+) -> Result<(Envelope, usize), ParseError> {
+    let (sig, payload, data) = crate::split_signed_request(signed_request)?;
+
+    let envelope: Envelope =
+        serde_json::from_slice(&data).map_err(ParseError::PayloadJsonDecodeFailed)?;
+    if envelope.algorithm != ENCRYPTED_ALGORITHM {
+        return Err(ParseError::AlgorithmUnknown(envelope.algorithm));
+    }
+
+    let i = crate::verify_signature(&sig, payload, app_secrets)?;
+    Ok((envelope, i))
+}
+
+fn decrypt<T: DeserializeOwned>(envelope: &Envelope, app_secret: &str) -> Result<T, ParseError> {
+    let iv = general_purpose::URL_SAFE_NO_PAD
+        .decode(&envelope.iv)
+        .map_err(ParseError::PayloadBase64DecodeFailed)?;
+    let encrypted = general_purpose::URL_SAFE_NO_PAD
+        .decode(&envelope.payload)
+        .map_err(ParseError::PayloadBase64DecodeFailed)?;
+
+    let data = Aes256CbcDec::new_from_slices(app_secret.as_bytes(), &iv)
+        .map_err(|_| ParseError::DecryptFailed)?
+        .decrypt_padded_vec_mut::<Pkcs7>(&encrypted)
+        .map_err(|_| ParseError::DecryptFailed)?;
+
+    serde_json::from_slice(&data).map_err(ParseError::PayloadJsonDecodeFailed)
+}
+
+/// The reverse of [`parse`].
+///
+/// `iv` should be random.
+pub fn encode<T: Serialize>(
+    payload: &T,
+    app_secret: &str,
+    iv: [u8; 16],
+    issued_at: i64,
+) -> Result<String, EncodeError> {
+    let data = serde_json::to_vec(payload).map_err(EncodeError::PayloadJsonEncodeFailed)?;
+    let encrypted = Aes256CbcEnc::new_from_slices(app_secret.as_bytes(), &iv)
+        .map_err(|_| EncodeError::EncryptFailed)?
+        .encrypt_padded_vec_mut::<Pkcs7>(&data);
+
+    let envelope = Envelope {
+        algorithm: ENCRYPTED_ALGORITHM.to_owned(),
+        issued_at,
+        iv: general_purpose::URL_SAFE_NO_PAD.encode(iv),
+        payload: general_purpose::URL_SAFE_NO_PAD.encode(encrypted),
+    };
+
+    crate::sign_payload(&envelope, app_secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::time::Duration;
+    use std::time::UNIX_EPOCH;
+
+    #[derive(Deserialize, Serialize, Debug)]
+    struct MyPayload {
+        user_id: String,
+        oauth_token: String,
+    }
+
+    const APP_SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_encode_and_parse() {
+        let payload = MyPayload {
+            user_id: "218471".to_owned(),
+            oauth_token: "foo".to_owned(),
+        };
+        let signed_request = encode(&payload, APP_SECRET, [1; 16], 1291836800).unwrap();
+
+        match parse::<MyPayload>(&signed_request, APP_SECRET) {
+            Ok(payload) => {
+                assert_eq!(payload.user_id, "218471");
+                assert_eq!(payload.oauth_token, "foo");
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        match parse_with_secrets::<MyPayload, _>(
+            &signed_request,
+            &["fedcba9876543210fedcba9876543210", APP_SECRET],
+        ) {
+            Ok((_, i)) => assert_eq!(i, 1),
+            Err(err) => panic!("{}", err),
+        }
+
+        match parse::<MyPayload>(&signed_request, "fedcba9876543210fedcba9876543210") {
+            Err(ParseError::SignatureMismatch) => {}
+            x => panic!("{:?}", x),
+        }
+
+        // Not a key of AES-256.
+        let signed_request = encode(&payload, "key", [1; 16], 1291836800);
+        match signed_request {
+            Err(EncodeError::EncryptFailed) => {}
+            x => panic!("{:?}", x),
+        }
+
+        // Not encrypted.
+        let signed_request = crate::encode(
+            &serde_json::json!({"algorithm": "HMAC-SHA256", "issued_at": 1291836800, "iv": "", "payload": ""}),
+            APP_SECRET,
+            crate::NORMALLY_ALGORITHM,
+        )
+        .unwrap();
+        match parse::<MyPayload>(&signed_request, APP_SECRET) {
+            Err(ParseError::AlgorithmUnknown(algorithm)) => assert_eq!(algorithm, "HMAC-SHA256"),
+            x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_parse_with_options() {
+        let payload = MyPayload {
+            user_id: "218471".to_owned(),
+            oauth_token: "foo".to_owned(),
+        };
+        let signed_request = encode(&payload, APP_SECRET, [2; 16], 1291836800).unwrap();
+        let options = ParseOptions::default()
+            .with_max_age(Duration::from_secs(60))
+            .with_clock_skew(Duration::ZERO);
+
+        let clock = |secs: u64| move || UNIX_EPOCH + Duration::from_secs(secs);
+        match parse_with_options::<MyPayload, _>(
+            &signed_request,
+            &[APP_SECRET],
+            &options.clone().with_clock(clock(1291836860)),
+        ) {
+            Ok((payload, _)) => assert_eq!(payload.user_id, "218471"),
+            Err(err) => panic!("{}", err),
+        }
+        match parse_with_options::<MyPayload, _>(
+            &signed_request,
+            &[APP_SECRET],
+            &options.with_clock(clock(1291836861)),
+        ) {
+            Err(ParseError::Expired) => {}
+            x => panic!("{:?}", x),
+        }
+    }
+}
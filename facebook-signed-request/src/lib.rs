@@ -1,18 +1,37 @@
+use core::{fmt, time::Duration};
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use base64::{engine::general_purpose, Engine as _};
 use hmac::{Hmac, Mac as _};
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 use sha2::Sha256;
 
 type HmacSha256 = Hmac<Sha256>;
 
 #[cfg(feature = "with-data-deletion-callback")]
 pub mod data_deletion_callback;
+#[cfg(feature = "with-encrypted")]
+pub mod encrypted;
 #[cfg(feature = "with-fb-login-deauth-callback")]
 pub mod fb_login_deauth_callback;
 #[cfg(feature = "with-ig-basic-display-data-deletion-request")]
 pub mod ig_basic_display_data_deletion_request;
 #[cfg(feature = "with-ig-basic-display-deauth-callback")]
 pub mod ig_basic_display_deauth_callback;
+#[cfg(feature = "with-login")]
+pub mod login;
+
+#[cfg(any(
+    feature = "with-fb-login-deauth-callback",
+    feature = "with-data-deletion-callback",
+    feature = "with-ig-basic-display-deauth-callback",
+    feature = "with-ig-basic-display-data-deletion-request",
+    feature = "with-login",
+))]
+mod serde_ext;
 
 pub const NORMALLY_ALGORITHM: &str = "HMAC-SHA256";
 
@@ -20,10 +39,125 @@ pub trait Payload: DeserializeOwned {
     fn algorithm(&self) -> Option<&str> {
         None
     }
+    /// Unix timestamp in seconds.
+    fn issued_at(&self) -> Option<i64> {
+        None
+    }
+    /// Unix timestamp in seconds.
+    fn expires(&self) -> Option<i64> {
+        None
+    }
+}
+
+pub type Clock = Arc<dyn Fn() -> SystemTime + Send + Sync>;
+
+/// Freshness checks of [`parse_with_options`].
+#[derive(Clone)]
+pub struct ParseOptions {
+    /// Reject when `issued_at` is older, `None` means don't check.
+    pub max_age: Option<Duration>,
+    /// Tolerated difference between Facebook's clock and `clock`.
+    pub clock_skew: Duration,
+    pub clock: Clock,
+}
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            clock_skew: Duration::from_secs(60 * 5),
+            clock: Arc::new(SystemTime::now),
+        }
+    }
+}
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseOptions")
+            .field("max_age", &self.max_age)
+            .field("clock_skew", &self.clock_skew)
+            .finish_non_exhaustive()
+    }
+}
+impl ParseOptions {
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn with_clock_skew(mut self, clock_skew: Duration) -> Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: impl Fn() -> SystemTime + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    fn check<T: Payload>(&self, data: &T) -> Result<(), ParseError> {
+        let secs = |dur: Duration| i64::try_from(dur.as_secs()).unwrap_or(i64::MAX);
+        let now = match (self.clock)().duration_since(UNIX_EPOCH) {
+            Ok(dur) => secs(dur),
+            Err(err) => -secs(err.duration()),
+        };
+        let clock_skew = secs(self.clock_skew);
+
+        if let Some(issued_at) = data.issued_at() {
+            if issued_at > now.saturating_add(clock_skew) {
+                return Err(ParseError::IssuedInFuture);
+            }
+            if let Some(max_age) = self.max_age {
+                if now
+                    > issued_at
+                        .saturating_add(secs(max_age))
+                        .saturating_add(clock_skew)
+                {
+                    return Err(ParseError::Expired);
+                }
+            }
+        }
+        if let Some(expires) = data.expires() {
+            if now > expires.saturating_add(clock_skew) {
+                return Err(ParseError::Expired);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// [Official doc](https://developers.facebook.com/docs/games/gamesonfacebook/login#parsingsr)
+///
+/// `issued_at` and `expires` aren't checked, see [`parse_with_options`].
+///
+/// `AES-256-CBC HMAC-SHA256` is `AlgorithmUnknown`, see `encrypted::parse` of the `with-encrypted` feature.
 pub fn parse<T: Payload>(signed_request: &str, app_secret: &str) -> Result<T, ParseError> {
+    parse_with_secrets(signed_request, &[app_secret]).map(|(data, _)| data)
+}
+
+/// Same as [`parse`], also returns the index of the first matched app secret.
+///
+/// e.g. `&[new_app_secret, old_app_secret]` while the app secret is being reset.
+pub fn parse_with_secrets<T: Payload, S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+) -> Result<(T, usize), ParseError> {
+    let (sig, payload, data) = split_signed_request(signed_request)?;
+
+    let data: T = serde_json::from_slice(&data).map_err(ParseError::PayloadJsonDecodeFailed)?;
+
+    let algorithm = data.algorithm().unwrap_or(NORMALLY_ALGORITHM);
+
+    match algorithm {
+        NORMALLY_ALGORITHM => {
+            let i = verify_signature(&sig, payload, app_secrets)?;
+            Ok((data, i))
+        }
+        _ => Err(ParseError::AlgorithmUnknown(algorithm.to_owned())),
+    }
+}
+
+/// The decoded signature, the encoded payload and the decoded payload.
+fn split_signed_request(signed_request: &str) -> Result<(Vec<u8>, &str, Vec<u8>), ParseError> {
     let mut signed_request_split = signed_request.split('.');
     let encoded_sig = signed_request_split
         .next()
@@ -42,21 +176,37 @@ pub fn parse<T: Payload>(signed_request: &str, app_secret: &str) -> Result<T, Pa
         .decode(payload)
         .map_err(ParseError::EncodedSignatureBase64DecodeFailed)?;
 
-    let data: T = serde_json::from_slice(&data).map_err(ParseError::PayloadJsonDecodeFailed)?;
-
-    let algorithm = data.algorithm().unwrap_or(NORMALLY_ALGORITHM);
-
-    let expected_sig = match algorithm {
-        NORMALLY_ALGORITHM => hmac_sha256_payload(payload.as_bytes(), app_secret)
-            .map_err(|_| ParseError::SignatureCalculateFailed)?,
-        _ => return Err(ParseError::AlgorithmUnknown(algorithm.to_owned())),
-    };
+    Ok((sig, payload, data))
+}
 
-    if sig != expected_sig {
-        return Err(ParseError::SignatureMismatch);
+/// The index of the first matched app secret.
+fn verify_signature<S: AsRef<str>>(
+    sig: &[u8],
+    payload: &str,
+    app_secrets: &[S],
+) -> Result<usize, ParseError> {
+    for (i, app_secret) in app_secrets.iter().enumerate() {
+        if hmac_sha256_payload(payload.as_bytes(), app_secret.as_ref())
+            .map_err(|_| ParseError::SignatureCalculateFailed)?
+            .verify_slice(sig)
+            .is_ok()
+        {
+            return Ok(i);
+        }
     }
 
-    Ok(data)
+    Err(ParseError::SignatureMismatch)
+}
+
+/// Same as [`parse_with_secrets`], then reject the stale or the replayed signed requests.
+pub fn parse_with_options<T: Payload, S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+    options: &ParseOptions,
+) -> Result<(T, usize), ParseError> {
+    let (data, app_secret_index) = parse_with_secrets::<T, S>(signed_request, app_secrets)?;
+    options.check(&data)?;
+    Ok((data, app_secret_index))
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -79,18 +229,63 @@ pub enum ParseError {
     SignatureCalculateFailed,
     #[error("SignatureMismatch")]
     SignatureMismatch,
+    #[error("Expired")]
+    Expired,
+    #[error("IssuedInFuture")]
+    IssuedInFuture,
+    /// Only by the `encrypted` module, kept regardless of the `with-encrypted` feature so the features stay additive.
+    #[error("DecryptFailed")]
+    DecryptFailed,
+}
+
+/// The reverse of [`parse`], e.g. to build the fixtures of the callback endpoints.
+///
+/// `algorithm` should be the same as the one in `payload`.
+pub fn encode<T: Serialize>(
+    payload: &T,
+    app_secret: &str,
+    algorithm: &str,
+) -> Result<String, EncodeError> {
+    match algorithm {
+        NORMALLY_ALGORITHM => sign_payload(payload, app_secret),
+        _ => Err(EncodeError::AlgorithmUnknown(algorithm.to_owned())),
+    }
+}
+
+fn sign_payload<T: Serialize>(payload: &T, app_secret: &str) -> Result<String, EncodeError> {
+    let data = serde_json::to_vec(payload).map_err(EncodeError::PayloadJsonEncodeFailed)?;
+    let payload = general_purpose::URL_SAFE_NO_PAD.encode(data);
+
+    let sig = hmac_sha256_payload(payload.as_bytes(), app_secret)
+        .map_err(|_| EncodeError::SignatureCalculateFailed)?
+        .finalize()
+        .into_bytes();
+    let encoded_sig = general_purpose::URL_SAFE_NO_PAD.encode(sig);
+
+    Ok(format!("{encoded_sig}.{payload}"))
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EncodeError {
+    #[error("PayloadJsonEncodeFailed {0}")]
+    PayloadJsonEncodeFailed(serde_json::Error),
+    #[error("AlgorithmUnknown {0}")]
+    AlgorithmUnknown(String),
+    #[error("SignatureCalculateFailed")]
+    SignatureCalculateFailed,
+    /// Only by the `encrypted` module.
+    #[error("EncryptFailed")]
+    EncryptFailed,
 }
 
 // $ echo -n "value" | openssl sha256 -hmac "key"
 // (stdin)= 90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481
-fn hmac_sha256_payload(payload_bytes: &[u8], app_secret: &str) -> Result<Vec<u8>, String> {
+fn hmac_sha256_payload(payload_bytes: &[u8], app_secret: &str) -> Result<HmacSha256, String> {
     let mut hmac =
         HmacSha256::new_from_slice(app_secret.as_bytes()).map_err(|err| err.to_string())?;
     hmac.update(payload_bytes);
-    let hmac_result = hmac.finalize().into_bytes();
-    let sig = hmac_result.to_vec();
 
-    Ok(sig)
+    Ok(hmac)
 }
 
 #[cfg(test)]
@@ -99,20 +294,23 @@ mod tests {
 
     use serde::Deserialize;
 
-    #[test]
-    fn test_parse() {
-        #[derive(Deserialize)]
-        struct MyPayload {
-            user_id: String,
-            algorithm: String,
-            issued_at: u64,
-        }
-        impl Payload for MyPayload {
-            fn algorithm(&self) -> Option<&str> {
-                Some(&self.algorithm)
-            }
+    #[derive(Deserialize, Serialize)]
+    struct MyPayload {
+        user_id: String,
+        algorithm: String,
+        issued_at: u64,
+    }
+    impl Payload for MyPayload {
+        fn algorithm(&self) -> Option<&str> {
+            Some(&self.algorithm)
         }
+        fn issued_at(&self) -> Option<i64> {
+            Some(self.issued_at as i64)
+        }
+    }
 
+    #[test]
+    fn test_parse() {
         // echo -n '{"user_id":"0","algorithm":"HMAC-SHA256","issued_at":1624244156}' | base64 | tr '+/' '-_' | tr -d '='
         // echo -n 'eyJ1c2VyX2lkIjoiMCIsImFsZ29yaXRobSI6IkhNQUMtU0hBMjU2IiwiaXNzdWVkX2F0IjoxNjI0MjQ0MTU2fQ' | openssl sha256 -hmac "key" -binary | base64 | tr '+/' '-_' | tr -d '='
         let signed_request = "Mf_s6nTb38UYqioBmPqu0Ewm9souPZB9I2fIGwV729U.eyJ1c2VyX2lkIjoiMCIsImFsZ29yaXRobSI6IkhNQUMtU0hBMjU2IiwiaXNzdWVkX2F0IjoxNjI0MjQ0MTU2fQ";
@@ -125,12 +323,148 @@ mod tests {
             }
             Err(err) => panic!("{}", err),
         }
+
+        // wrong key
+        match parse::<MyPayload>(signed_request, "other") {
+            Err(ParseError::SignatureMismatch) => {}
+            Ok(_) => panic!(""),
+            Err(err) => panic!("{}", err),
+        }
+
+        // wrong-length digest
+        match parse::<MyPayload>(
+            signed_request
+                .replacen("Mf_s6nTb38UYqioBmPqu0Ewm9souPZB9I2fIGwV729U", "Mf_s6nTb", 1)
+                .as_str(),
+            "key",
+        ) {
+            Err(ParseError::SignatureMismatch) => {}
+            Ok(_) => panic!(""),
+            Err(err) => panic!("{}", err),
+        }
+
+        // bad base64
+        match parse::<MyPayload>(
+            signed_request.replacen("Mf_s6nTb", "Mf_s6nT*", 1).as_str(),
+            "key",
+        ) {
+            Err(ParseError::EncodedSignatureBase64DecodeFailed(_)) => {}
+            Ok(_) => panic!(""),
+            Err(err) => panic!("{}", err),
+        }
+
+        // multiple secrets
+        match parse_with_secrets::<MyPayload, _>(signed_request, &["other", "key"]) {
+            Ok((payload, i)) => {
+                assert_eq!(payload.user_id, "0");
+                assert_eq!(i, 1);
+            }
+            Err(err) => panic!("{}", err),
+        }
+        match parse_with_secrets::<MyPayload, _>(signed_request, &["other"]) {
+            Err(ParseError::SignatureMismatch) => {}
+            Ok(_) => panic!(""),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let payload = MyPayload {
+            user_id: "0".to_owned(),
+            algorithm: NORMALLY_ALGORITHM.to_owned(),
+            issued_at: 1624244156,
+        };
+
+        // Same as the one in test_parse.
+        assert_eq!(
+            encode(&payload, "key", NORMALLY_ALGORITHM).unwrap(),
+            "Mf_s6nTb38UYqioBmPqu0Ewm9souPZB9I2fIGwV729U.eyJ1c2VyX2lkIjoiMCIsImFsZ29yaXRobSI6IkhNQUMtU0hBMjU2IiwiaXNzdWVkX2F0IjoxNjI0MjQ0MTU2fQ"
+        );
+
+        let signed_request = encode(&payload, "key", NORMALLY_ALGORITHM).unwrap();
+        let (parsed, i) =
+            parse_with_secrets::<MyPayload, _>(&signed_request, &["other", "key"]).unwrap();
+        assert_eq!(parsed.issued_at, payload.issued_at);
+        assert_eq!(i, 1);
+
+        match encode(&payload, "key", "AES-256-CBC HMAC-SHA256") {
+            Err(EncodeError::AlgorithmUnknown(_)) => {}
+            x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_parse_with_options() {
+        let signed_request = "Mf_s6nTb38UYqioBmPqu0Ewm9souPZB9I2fIGwV729U.eyJ1c2VyX2lkIjoiMCIsImFsZ29yaXRobSI6IkhNQUMtU0hBMjU2IiwiaXNzdWVkX2F0IjoxNjI0MjQ0MTU2fQ";
+        let at = |secs: u64| move || UNIX_EPOCH + Duration::from_secs(secs);
+        let options = ParseOptions::default()
+            .with_max_age(Duration::from_secs(60))
+            .with_clock_skew(Duration::from_secs(10));
+
+        for now in [1624244146, 1624244156, 1624244226] {
+            match parse_with_options::<MyPayload, _>(
+                signed_request,
+                &["key"],
+                &options.clone().with_clock(at(now)),
+            ) {
+                Ok((payload, _)) => assert_eq!(payload.issued_at, 1624244156),
+                Err(err) => panic!("{} {}", now, err),
+            }
+        }
+
+        match parse_with_options::<MyPayload, _>(
+            signed_request,
+            &["key"],
+            &options.clone().with_clock(at(1624244145)),
+        ) {
+            Err(ParseError::IssuedInFuture) => {}
+            x => panic!("{:?}", x.map(|_| ())),
+        }
+        match parse_with_options::<MyPayload, _>(
+            signed_request,
+            &["key"],
+            &options.clone().with_clock(at(1624244227)),
+        ) {
+            Err(ParseError::Expired) => {}
+            x => panic!("{:?}", x.map(|_| ())),
+        }
+
+        // Never expire, and no overflow.
+        for options in [
+            options.clone().with_max_age(Duration::MAX),
+            options.clone().with_clock_skew(Duration::MAX),
+        ] {
+            match parse_with_options::<MyPayload, _>(
+                signed_request,
+                &["key"],
+                &options.with_clock(at(1624244156 + 86400 * 365)),
+            ) {
+                Ok((payload, _)) => assert_eq!(payload.issued_at, 1624244156),
+                Err(err) => panic!("{}", err),
+            }
+        }
+
+        // The signature is checked first.
+        match parse_with_options::<MyPayload, _>(
+            signed_request,
+            &["other"],
+            &options.with_clock(at(0)),
+        ) {
+            Err(ParseError::SignatureMismatch) => {}
+            x => panic!("{:?}", x.map(|_| ())),
+        }
     }
 
     #[test]
     fn test_hmac_sha256_payload() {
         assert_eq!(
-            hex::encode(hmac_sha256_payload(b"value", "key").unwrap()),
+            hex::encode(
+                hmac_sha256_payload(b"value", "key")
+                    .unwrap()
+                    .finalize()
+                    .into_bytes()
+            ),
             "90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481"
         );
     }
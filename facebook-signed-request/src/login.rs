@@ -0,0 +1,375 @@
+//! [Official doc](https://developers.facebook.com/docs/reference/login/signed-request)
+//!
+//! Passed to the Canvas and Page Tab apps, and returned by the JavaScript SDK login.
+//! The JavaScript SDK also stores it in the `fbsr_{app_id}` cookie, see [`parse_cookie_header`],
+//! or [`parse_cookie_header_with_options`] to reject the stale cookies and to try several app secrets.
+
+use chrono::{
+    serde::{ts_seconds, ts_seconds_option},
+    DateTime, Utc,
+};
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+};
+
+use crate::{
+    serde_ext::{serialize_option_to_string, serialize_to_string},
+    EncodeError, ParseError, ParseOptions, NORMALLY_ALGORITHM,
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Payload {
+    pub algorithm: String,
+    #[serde(with = "ts_seconds")]
+    pub issued_at: DateTime<Utc>,
+    /// `None` when the user hasn't logged in to the app.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub user_id: Option<u64>,
+    /// Exchangeable for a user access token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth_token: Option<String>,
+    /// When the `oauth_token` expires.
+    #[serde(
+        default,
+        with = "ts_seconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expires: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+    /// Only in the Page Tab apps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<Page>,
+    /// The `app_data` query parameter of the Page Tab url.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_data: Option<String>,
+}
+impl Payload {
+    pub fn new(issued_at: DateTime<Utc>) -> Self {
+        Self {
+            algorithm: NORMALLY_ALGORITHM.to_owned(),
+            issued_at,
+            user_id: None,
+            code: None,
+            oauth_token: None,
+            expires: None,
+            user: None,
+            page: None,
+            app_data: None,
+        }
+    }
+}
+impl crate::Payload for Payload {
+    fn algorithm(&self) -> Option<&str> {
+        Some(&self.algorithm)
+    }
+    fn issued_at(&self) -> Option<i64> {
+        Some(self.issued_at.timestamp())
+    }
+    // `expires` is of the `oauth_token`, not of the signed request.
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct User {
+    pub country: String,
+    pub locale: String,
+    pub age: UserAge,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UserAge {
+    pub min: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Page {
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+    pub liked: bool,
+    pub admin: bool,
+}
+
+pub fn parse(signed_request: &str, app_secret: &str) -> Result<Payload, ParseError> {
+    crate::parse(signed_request, app_secret)
+}
+
+pub fn parse_with_secrets<S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+) -> Result<(Payload, usize), ParseError> {
+    crate::parse_with_secrets(signed_request, app_secrets)
+}
+
+pub fn parse_with_options<S: AsRef<str>>(
+    signed_request: &str,
+    app_secrets: &[S],
+    options: &ParseOptions,
+) -> Result<(Payload, usize), ParseError> {
+    crate::parse_with_options(signed_request, app_secrets, options)
+}
+
+pub fn encode(payload: &Payload, app_secret: &str) -> Result<String, EncodeError> {
+    crate::encode(payload, app_secret, &payload.algorithm)
+}
+
+pub const COOKIE_NAME_PREFIX: &str = "fbsr_";
+
+pub fn cookie_name(app_id: u64) -> String {
+    format!("{COOKIE_NAME_PREFIX}{app_id}")
+}
+
+/// `cookies` are the name and value pairs, e.g. of a cookie jar.
+pub fn find_cookie<'a>(
+    cookies: impl IntoIterator<Item = (&'a str, &'a str)>,
+    app_id: u64,
+) -> Option<&'a str> {
+    let name = cookie_name(app_id);
+    cookies
+        .into_iter()
+        .find(|(k, _)| k.trim() == name)
+        .map(|(_, v)| {
+            let v = v.trim();
+            v.strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(v)
+        })
+        .filter(|v| !v.is_empty())
+}
+
+/// `cookie_header` is the value of the `Cookie` header.
+pub fn find_cookie_in_header(cookie_header: &str, app_id: u64) -> Option<&str> {
+    find_cookie(
+        cookie_header
+            .split(';')
+            .filter_map(|pair| pair.split_once('=')),
+        app_id,
+    )
+}
+
+pub fn parse_cookie<'a>(
+    cookies: impl IntoIterator<Item = (&'a str, &'a str)>,
+    app_id: u64,
+    app_secret: &str,
+) -> Result<Payload, ParseCookieError> {
+    let signed_request = find_cookie(cookies, app_id).ok_or(ParseCookieError::CookieMissing)?;
+    parse(signed_request, app_secret).map_err(Into::into)
+}
+
+pub fn parse_cookie_header(
+    cookie_header: &str,
+    app_id: u64,
+    app_secret: &str,
+) -> Result<Payload, ParseCookieError> {
+    let signed_request =
+        find_cookie_in_header(cookie_header, app_id).ok_or(ParseCookieError::CookieMissing)?;
+    parse(signed_request, app_secret).map_err(Into::into)
+}
+
+/// Same as [`parse_cookie`], with [`parse_with_options`].
+pub fn parse_cookie_with_options<'a, S: AsRef<str>>(
+    cookies: impl IntoIterator<Item = (&'a str, &'a str)>,
+    app_id: u64,
+    app_secrets: &[S],
+    options: &ParseOptions,
+) -> Result<(Payload, usize), ParseCookieError> {
+    let signed_request = find_cookie(cookies, app_id).ok_or(ParseCookieError::CookieMissing)?;
+    parse_with_options(signed_request, app_secrets, options).map_err(Into::into)
+}
+
+/// Same as [`parse_cookie_header`], with [`parse_with_options`].
+pub fn parse_cookie_header_with_options<S: AsRef<str>>(
+    cookie_header: &str,
+    app_id: u64,
+    app_secrets: &[S],
+    options: &ParseOptions,
+) -> Result<(Payload, usize), ParseCookieError> {
+    let signed_request =
+        find_cookie_in_header(cookie_header, app_id).ok_or(ParseCookieError::CookieMissing)?;
+    parse_with_options(signed_request, app_secrets, options).map_err(Into::into)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParseCookieError {
+    #[error("CookieMissing")]
+    CookieMissing,
+    #[error("ParseFailed {0}")]
+    ParseFailed(#[from] ParseError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_de() {
+        let json = r#"{"oauth_token":"{user-access-token}","algorithm":"HMAC-SHA256","expires":1291840400,"issued_at":1291836800,"user_id":"218471"}"#;
+
+        match serde_json::from_str::<Payload>(json) {
+            Ok(payload) => {
+                assert_eq!(payload.algorithm, "HMAC-SHA256");
+                assert_eq!(payload.issued_at.timestamp(), 1291836800);
+                assert_eq!(payload.user_id, Some(218471));
+                assert_eq!(payload.oauth_token.as_deref(), Some("{user-access-token}"));
+                assert_eq!(payload.expires.map(|x| x.timestamp()), Some(1291840400));
+                assert!(payload.user.is_none());
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"{"algorithm":"HMAC-SHA256","issued_at":1291836800,"user":{"country":"us","locale":"en_US","age":{"min":21}},"page":{"id":"1600000000000000","liked":true,"admin":false},"app_data":"foo"}"#;
+
+        match serde_json::from_str::<Payload>(json) {
+            Ok(payload) => {
+                assert!(payload.user_id.is_none());
+                assert!(payload.expires.is_none());
+                let user = payload.user.unwrap();
+                assert_eq!(user.country, "us");
+                assert_eq!(user.locale, "en_US");
+                assert_eq!(user.age.min, 21);
+                assert!(user.age.max.is_none());
+                let page = payload.page.unwrap();
+                assert_eq!(page.id, 1600000000000000);
+                assert!(page.liked);
+                assert!(!page.admin);
+                assert_eq!(payload.app_data.as_deref(), Some("foo"));
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_encode_and_parse() {
+        use chrono::TimeZone as _;
+
+        let mut payload = Payload::new(Utc.timestamp_opt(1291836800, 0).unwrap());
+        payload.user_id = Some(218471);
+        payload.code = Some("foo".to_owned());
+
+        let signed_request = encode(&payload, "key").unwrap();
+        match parse(&signed_request, "key") {
+            Ok(payload) => {
+                assert_eq!(payload.user_id, Some(218471));
+                assert_eq!(payload.code.as_deref(), Some("foo"));
+                assert!(payload.oauth_token.is_none());
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn test_parse_cookie_header() {
+        use chrono::TimeZone as _;
+
+        let mut payload = Payload::new(Utc.timestamp_opt(1291836800, 0).unwrap());
+        payload.user_id = Some(218471);
+        payload.code = Some("foo".to_owned());
+        let signed_request = encode(&payload, "key").unwrap();
+
+        let cookie_header =
+            format!("fbm_123=base_domain=; fbsr_1234=bar; fbsr_123={signed_request}; locale=en");
+
+        assert_eq!(
+            find_cookie_in_header(&cookie_header, 123),
+            Some(signed_request.as_str())
+        );
+        assert_eq!(
+            find_cookie_in_header(&format!(r#"fbsr_123="{signed_request}""#), 123),
+            Some(signed_request.as_str())
+        );
+        assert_eq!(find_cookie_in_header("fbsr_123=", 123), None);
+
+        match parse_cookie_header(&cookie_header, 123, "key") {
+            Ok(payload) => {
+                assert_eq!(payload.user_id, Some(218471));
+                assert_eq!(payload.code.as_deref(), Some("foo"));
+            }
+            Err(err) => panic!("{}", err),
+        }
+        match parse_cookie([("fbsr_123", signed_request.as_str())], 123, "key") {
+            Ok(payload) => assert_eq!(payload.user_id, Some(218471)),
+            Err(err) => panic!("{}", err),
+        }
+
+        match parse_cookie_header(&cookie_header, 12, "key") {
+            Err(ParseCookieError::CookieMissing) => {}
+            x => panic!("{:?}", x),
+        }
+        match parse_cookie_header(&cookie_header, 123, "other") {
+            Err(ParseCookieError::ParseFailed(ParseError::SignatureMismatch)) => {}
+            x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_parse_cookie_with_options() {
+        use core::time::Duration;
+        use std::time::UNIX_EPOCH;
+
+        use chrono::TimeZone as _;
+
+        let mut payload = Payload::new(Utc.timestamp_opt(1291836800, 0).unwrap());
+        payload.user_id = Some(218471);
+        let signed_request = encode(&payload, "key").unwrap();
+        let cookie_header = format!("fbsr_123={signed_request}; locale=en");
+
+        let options = ParseOptions::default()
+            .with_max_age(Duration::from_secs(60))
+            .with_clock_skew(Duration::ZERO);
+        let clock = |secs: u64| move || UNIX_EPOCH + Duration::from_secs(secs);
+
+        match parse_cookie_header_with_options(
+            &cookie_header,
+            123,
+            &["other", "key"],
+            &options.clone().with_clock(clock(1291836860)),
+        ) {
+            Ok((payload, i)) => {
+                assert_eq!(payload.user_id, Some(218471));
+                assert_eq!(i, 1);
+            }
+            Err(err) => panic!("{}", err),
+        }
+        match parse_cookie_with_options(
+            [("fbsr_123", signed_request.as_str())],
+            123,
+            &["key"],
+            &options.clone().with_clock(clock(1291836860)),
+        ) {
+            Ok((_, i)) => assert_eq!(i, 0),
+            Err(err) => panic!("{}", err),
+        }
+
+        // older than max_age
+        match parse_cookie_header_with_options(
+            &cookie_header,
+            123,
+            &["key"],
+            &options.clone().with_clock(clock(1291836861)),
+        ) {
+            Err(ParseCookieError::ParseFailed(ParseError::Expired)) => {}
+            x => panic!("{:?}", x),
+        }
+        match parse_cookie_with_options(
+            [("fbsr_123", signed_request.as_str())],
+            123,
+            &["key"],
+            &options.with_clock(clock(1291836861)),
+        ) {
+            Err(ParseCookieError::ParseFailed(ParseError::Expired)) => {}
+            x => panic!("{:?}", x),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use core::fmt::Display;
+
+use serde::Serializer;
+
+/// Facebook sends the ids as strings.
+pub(crate) fn serialize_to_string<T, S>(v: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    serializer.collect_str(v)
+}
+
+#[cfg(feature = "with-login")]
+pub(crate) fn serialize_option_to_string<T, S>(
+    v: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    match v {
+        Some(v) => serializer.collect_str(v),
+        None => serializer.serialize_none(),
+    }
+}
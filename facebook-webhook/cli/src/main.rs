@@ -0,0 +1,395 @@
+/*
+cargo run -p facebook-webhook-cli -- sign --app-secret YOUR_APP_SECRET payload.json
+cargo run -p facebook-webhook-cli -- send --app-secret YOUR_APP_SECRET http://127.0.0.1:4001/fb_webhooks/202000000000000 payload.json
+cargo run -p facebook-webhook-cli -- verify-challenge --verify-token YOUR_VERIFY_TOKEN http://127.0.0.1:4001/fb_webhooks/202000000000000
+cargo run -p facebook-webhook-cli -- replay --app-secret YOUR_APP_SECRET http://127.0.0.1:4001/fb_webhooks/202000000000000 deliveries.jsonl
+*/
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use facebook_webhook::{
+    event_notifications::{sign_payload, Payload, SignatureAlgorithm},
+    spool,
+    verification_requests::Query,
+};
+use hyper::{body, header::CONTENT_TYPE, Body, Client, Method, Request, StatusCode};
+use serde::Deserialize;
+
+type Error = Box<dyn std::error::Error>;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "facebook-webhook-cli",
+    version,
+    about = "Sign and send webhook requests the way Facebook does"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the signature headers of the JSON file.
+    Sign {
+        #[arg(long, env = "APP_SECRET")]
+        app_secret: String,
+        #[arg(long, value_enum, default_value_t)]
+        signature: SignatureArg,
+        file: PathBuf,
+    },
+    /// POST the JSON file to the callback url, signed.
+    Send {
+        #[arg(long, env = "APP_SECRET")]
+        app_secret: String,
+        #[arg(long, value_enum, default_value_t)]
+        signature: SignatureArg,
+        url: String,
+        file: PathBuf,
+    },
+    /// GET the callback url with `hub.challenge`, and check that it's passed back.
+    VerifyChallenge {
+        #[arg(long, env = "VERIFY_TOKEN")]
+        verify_token: String,
+        /// Random when missing.
+        #[arg(long)]
+        challenge: Option<i64>,
+        url: String,
+    },
+    /// POST the captured deliveries to the callback url, one by one.
+    ///
+    /// `source` is a spool directory, or a JSON Lines file whose lines are `{"headers": {..}, "body": ".."}` or bare payloads.
+    Replay {
+        /// Re-sign the bodies, otherwise send the captured signature headers.
+        #[arg(long, env = "APP_SECRET")]
+        app_secret: Option<String>,
+        #[arg(long, value_enum, default_value_t)]
+        signature: SignatureArg,
+        /// Pause between deliveries.
+        #[arg(long, default_value_t = 0)]
+        interval_ms: u64,
+        url: String,
+        source: PathBuf,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+enum SignatureArg {
+    Sha1,
+    Sha256,
+    /// Like Facebook does.
+    #[default]
+    Both,
+}
+impl SignatureArg {
+    fn algorithms(&self) -> Vec<SignatureAlgorithm> {
+        match self {
+            Self::Sha1 => vec![SignatureAlgorithm::Sha1],
+            Self::Sha256 => vec![SignatureAlgorithm::Sha256],
+            Self::Both => vec![SignatureAlgorithm::Sha256, SignatureAlgorithm::Sha1],
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Delivery {
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(err) = run(Cli::parse()).await {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Error> {
+    match cli.command {
+        Command::Sign {
+            app_secret,
+            signature,
+            file,
+        } => {
+            let body = read_payload_file(&file).await?;
+            for (name, value) in sign(&body, &app_secret, signature)? {
+                println!("{name}: {value}");
+            }
+        }
+        Command::Send {
+            app_secret,
+            signature,
+            url,
+            file,
+        } => {
+            let body = read_payload_file(&file).await?;
+            let headers = sign(&body, &app_secret, signature)?;
+
+            let (status, res_body) = post(&url, headers, body).await?;
+            println!("{status} {res_body}");
+            if !status.is_success() {
+                return Err(format!("status {status}").into());
+            }
+        }
+        Command::VerifyChallenge {
+            verify_token,
+            challenge,
+            url,
+        } => {
+            let challenge = challenge.unwrap_or_else(random_challenge);
+            let query_str = Query::new(verify_token, challenge).to_query_string()?;
+            let separator = if url.contains('?') { '&' } else { '?' };
+
+            let res = Client::new()
+                .get(format!("{url}{separator}{query_str}").parse()?)
+                .await?;
+            let status = res.status();
+            let res_body =
+                String::from_utf8_lossy(&body::to_bytes(res.into_body()).await?).into_owned();
+            println!("{status} {res_body}");
+
+            if status != StatusCode::OK || res_body.trim() != challenge.to_string() {
+                return Err(format!("challenge {challenge} isn't passed back").into());
+            }
+        }
+        Command::Replay {
+            app_secret,
+            signature,
+            interval_ms,
+            url,
+            source,
+        } => {
+            let deliveries = read_deliveries(&source).await?;
+
+            let mut failed = 0;
+            for (i, delivery) in deliveries.into_iter().enumerate() {
+                if i > 0 && interval_ms > 0 {
+                    tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+                }
+
+                let headers = match &app_secret {
+                    Some(app_secret) => sign(delivery.body.as_bytes(), app_secret, signature)?,
+                    None => delivery.headers.into_iter().collect(),
+                };
+
+                let (status, res_body) = post(&url, headers, delivery.body.into_bytes()).await?;
+                println!("{i} {status} {res_body}");
+                if !status.is_success() {
+                    failed += 1;
+                }
+            }
+
+            if failed > 0 {
+                return Err(format!("{failed} deliveries failed").into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The bytes are kept as is, only checked that they are a [`Payload`].
+async fn read_payload_file(path: &Path) -> Result<Vec<u8>, Error> {
+    let body = tokio::fs::read(path).await?;
+    serde_json::from_slice::<Payload>(&body)
+        .map_err(|err| format!("{} isn't a payload: {err}", path.display()))?;
+    Ok(body)
+}
+
+async fn read_deliveries(source: &Path) -> Result<Vec<Delivery>, Error> {
+    if source.is_dir() {
+        let source = source.to_owned();
+        let entries = tokio::task::spawn_blocking(move || spool::read_entries(source)).await??;
+
+        return entries
+            .into_iter()
+            .map(|entry| {
+                Ok(Delivery {
+                    headers: entry
+                        .headers
+                        .into_iter()
+                        .map(|(name, value)| Ok((name, String::from_utf8(value)?)))
+                        .collect::<Result<_, Error>>()?,
+                    body: String::from_utf8(entry.body)?,
+                })
+            })
+            .collect();
+    }
+
+    let content = tokio::fs::read_to_string(source).await?;
+    parse_deliveries(source, &content)
+}
+
+/// A line that is neither a [`Delivery`] nor a [`Payload`] fails with its number.
+fn parse_deliveries(source: &Path, content: &str) -> Result<Vec<Delivery>, Error> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            if let Ok(delivery) = serde_json::from_str::<Delivery>(line) {
+                return Ok(delivery);
+            }
+            serde_json::from_str::<Payload>(line)
+                .map(|_| Delivery {
+                    headers: Default::default(),
+                    body: line.to_owned(),
+                })
+                .map_err(|err| {
+                    format!(
+                        "{}:{} is neither a delivery nor a payload: {err}",
+                        source.display(),
+                        i + 1
+                    )
+                    .into()
+                })
+        })
+        .collect()
+}
+
+fn sign(
+    body: &[u8],
+    app_secret: &str,
+    signature: SignatureArg,
+) -> Result<Vec<(String, String)>, Error> {
+    signature
+        .algorithms()
+        .into_iter()
+        .map(|algorithm| {
+            Ok((
+                algorithm.header_name().to_owned(),
+                sign_payload(body, app_secret, algorithm)?,
+            ))
+        })
+        .collect()
+}
+
+async fn post(
+    url: &str,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+) -> Result<(StatusCode, String), Error> {
+    let mut req = Request::builder()
+        .method(Method::POST)
+        .uri(url)
+        .header(CONTENT_TYPE, "application/json");
+    for (name, value) in headers {
+        req = req.header(name, value);
+    }
+
+    let res = Client::new().request(req.body(Body::from(body))?).await?;
+    let status = res.status();
+    let res_body = body::to_bytes(res.into_body()).await?;
+
+    Ok((status, String::from_utf8_lossy(&res_body).into_owned()))
+}
+
+fn random_challenge() -> i64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    1_000_000_000 + i64::from(nanos % 1_000_000_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use facebook_webhook::spool::{Spool, SpoolConfig};
+
+    const PAYLOAD: &str = r#"{"object":"user","entry":[]}"#;
+
+    #[test]
+    fn test_parse_deliveries() {
+        let source = Path::new("deliveries.jsonl");
+        let content = format!(
+            "{}\n\n{PAYLOAD}\n",
+            serde_json::json!({"headers": {"X-Hub-Signature-256": "sha256=00"}, "body": PAYLOAD})
+        );
+        match parse_deliveries(source, &content) {
+            Ok(deliveries) => {
+                assert_eq!(deliveries.len(), 2);
+                assert_eq!(
+                    deliveries[0].headers.get("X-Hub-Signature-256").unwrap(),
+                    "sha256=00"
+                );
+                assert_eq!(deliveries[0].body, PAYLOAD);
+                assert!(deliveries[1].headers.is_empty());
+                assert_eq!(deliveries[1].body, PAYLOAD);
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        let content = format!("{PAYLOAD}\n{{\"body\": \"\n");
+        match parse_deliveries(source, &content) {
+            Err(err) => assert!(
+                err.to_string()
+                    .starts_with("deliveries.jsonl:2 is neither a delivery nor a payload"),
+                "{}",
+                err
+            ),
+            Ok(x) => panic!("{:?}", x),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_deliveries_from_spool() {
+        let dir =
+            std::env::temp_dir().join(format!("facebook-webhook-cli-{}-spool", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let spool = Spool::open(SpoolConfig::new(&dir)).unwrap();
+        spool
+            .append(&[("X-Hub-Signature-256", b"sha256=00")], PAYLOAD.as_bytes())
+            .unwrap();
+        spool.append(&[], PAYLOAD.as_bytes()).unwrap();
+        drop(spool);
+
+        match read_deliveries(&dir).await {
+            Ok(deliveries) => {
+                assert_eq!(deliveries.len(), 2);
+                assert_eq!(
+                    deliveries[0].headers.get("X-Hub-Signature-256").unwrap(),
+                    "sha256=00"
+                );
+                assert_eq!(deliveries[1].body, PAYLOAD);
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sign() {
+        match sign(b"value", "key", SignatureArg::Sha256) {
+            Ok(headers) => assert_eq!(
+                headers,
+                vec![(
+                    "X-Hub-Signature-256".to_owned(),
+                    "sha256=90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481"
+                        .to_owned()
+                )]
+            ),
+            Err(err) => panic!("{}", err),
+        }
+
+        assert_eq!(
+            sign(b"value", "key", SignatureArg::Both)
+                .unwrap()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            vec!["X-Hub-Signature-256", "X-Hub-Signature"]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+pub use facebook_webhook;
+pub use facebook_webhook::context::{async_trait, CachedContext, Context, ContextError, Secret};
+
+use actix_web::{
+    web::{self, Bytes, Data, Path, PayloadConfig, Query},
+    HttpRequest, HttpResponse, Scope,
+};
+use facebook_webhook::{
+    event_notifications::{
+        self, PassBackCallbackFn, VerifyOptions, SIGNATURE_256_HEADER_NAME, SIGNATURE_HEADER_NAME,
+    },
+    verification_requests::{self, Query as VerificationQuery},
+};
+
+pub fn scope<C: Context + 'static>(
+    path_prefix: &str,
+    ctx: C,
+    callback: PassBackCallbackFn<'static, C>,
+) -> Scope {
+    web::scope(&format!("/{}", path_prefix))
+        .app_data(Data::new(ctx))
+        .app_data(Data::new(callback))
+        .app_data(PayloadConfig::new(1024 * 32))
+        .route(
+            "/{app_id}",
+            web::get().to(verification_requests_handler::<C>),
+        )
+        .route(
+            "/{app_id}",
+            web::post().to(event_notifications_handler::<C>),
+        )
+}
+
+async fn verification_requests_handler<C: Context + 'static>(
+    ctx: Data<C>,
+    app_id: Path<u64>,
+    query: Query<VerificationQuery>,
+) -> HttpResponse {
+    match ctx.get_verify_token(app_id.into_inner()).await {
+        Ok(verify_token) => {
+            let res = verification_requests::pass_back_with_query(
+                query.into_inner(),
+                &verify_token.value,
+            );
+
+            HttpResponse::build(res.status_code).body(res.body)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+async fn event_notifications_handler<C: Context + 'static>(
+    req: HttpRequest,
+    ctx: Data<C>,
+    callback: Data<PassBackCallbackFn<'static, C>>,
+    app_id: Path<u64>,
+    request_body_bytes: Bytes,
+) -> HttpResponse {
+    let app_id = app_id.into_inner();
+
+    match ctx.get_app_secrets(app_id).await {
+        Ok(app_secrets) => {
+            let options = VerifyOptions::new(&app_secrets)
+                .with_signature_headers(
+                    req.headers()
+                        .get(SIGNATURE_256_HEADER_NAME)
+                        .map(|x| x.as_bytes()),
+                    req.headers()
+                        .get(SIGNATURE_HEADER_NAME)
+                        .map(|x| x.as_bytes()),
+                )
+                .with_policy(ctx.get_signature_policy(app_id));
+            let res = event_notifications::pass_back(
+                &request_body_bytes[..],
+                &options,
+                ctx.get_ref().clone(),
+                callback.into_inner(),
+            )
+            .await;
+            if let Some(index) = res.app_secret_index {
+                ctx.on_app_secret_matched(app_id, index);
+            }
+
+            HttpResponse::build(res.status_code).body(res.body)
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{http::StatusCode, test, App};
+    use facebook_webhook::event_notifications::{sign_payload, Payload, SignatureAlgorithm};
+
+    #[derive(Debug, Clone)]
+    struct MyContext;
+
+    #[async_trait]
+    impl Context for MyContext {
+        async fn get_verify_token(&self, _app_id: u64) -> Result<Secret, ContextError> {
+            Ok(Secret::new("token"))
+        }
+
+        async fn get_app_secret(&self, _app_id: u64) -> Result<Secret, ContextError> {
+            Ok(Secret::new("key"))
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_scope() {
+        let app = test::init_service(App::new().service(scope(
+            "fb_webhooks",
+            MyContext,
+            Box::new(|payload, _ctx| {
+                Box::pin(async move {
+                    match payload {
+                        Payload::Other { object, .. } if object == "foo" => Err("foo".into()),
+                        _ => Ok(()),
+                    }
+                })
+            }),
+        )))
+        .await;
+
+        //
+        let req = test::TestRequest::get()
+            .uri(
+                "/fb_webhooks/1?hub.mode=subscribe&hub.challenge=1158201444&hub.verify_token=token",
+            )
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "1158201444");
+
+        let req = test::TestRequest::get()
+            .uri(
+                "/fb_webhooks/1?hub.mode=subscribe&hub.challenge=1158201444&hub.verify_token=other",
+            )
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        //
+        let post = |body: &'static str, app_secret: Option<&str>| {
+            let mut req = test::TestRequest::post()
+                .uri("/fb_webhooks/1")
+                .set_payload(body);
+            if let Some(app_secret) = app_secret {
+                req = req.insert_header((
+                    SIGNATURE_256_HEADER_NAME,
+                    sign_payload(body.as_bytes(), app_secret, SignatureAlgorithm::Sha256).unwrap(),
+                ));
+            }
+            req.to_request()
+        };
+
+        let res =
+            test::call_service(&app, post(r#"{"object":"bar","entry":[]}"#, Some("key"))).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res =
+            test::call_service(&app, post(r#"{"object":"foo","entry":[]}"#, Some("key"))).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(test::read_body(res).await, "foo");
+
+        let res = test::call_service(&app, post(r#"{"object":"bar","entry":[]}"#, None)).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res =
+            test::call_service(&app, post(r#"{"object":"bar","entry":[]}"#, Some("other"))).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(test::read_body(res).await, "SignatureMismatch");
+    }
+}
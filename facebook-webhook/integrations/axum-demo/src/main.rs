@@ -0,0 +1,108 @@
+/*
+sudo vim /etc/nginx/conf.d/xx.conf
+    location ~/fb_webhooks/(\d+) {
+        proxy_pass http://127.0.0.1:4001;
+    }
+sudo systemctl reload nginx
+
+cargo run -p facebook-webhook-axum-demo -- 202000000000000 YOUR_APP_SECRET
+*/
+
+use std::{env, time::Duration};
+
+use facebook_webhook_axum::{async_trait, CachedContext, Context, ContextError, Secret};
+use passwords::PasswordGenerator;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    run().await
+}
+
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let app_id: u64 = env::args()
+        .nth(1)
+        .or_else(|| env::var("APP_ID").ok())
+        .ok_or("app_id missing")?
+        .parse()?;
+    let app_secret = env::args()
+        .nth(2)
+        .or_else(|| env::var("APP_SECRET").ok())
+        .ok_or("app_secret missing")?;
+
+    let path_prefix: String = env::var("PATH_PREFIX").unwrap_or_else(|_| "fb_webhooks".to_owned());
+    let verify_token: String = env::var("VERIFY_TOKEN").unwrap_or_else(|_| {
+        PasswordGenerator {
+            length: 32,
+            numbers: true,
+            lowercase_letters: true,
+            uppercase_letters: true,
+            symbols: true,
+            spaces: false,
+            exclude_similar_characters: true,
+            strict: true,
+        }
+        .generate_one()
+        .unwrap()
+    });
+
+    let listen_port: u16 = env::var("LISTEN_PORT")
+        .unwrap_or_else(|_| "4001".to_owned())
+        .parse()?;
+
+    println!(
+        r#"app_id: "{}" path_prefix: "{}" verify_token: "{}" listen_port: {}"#,
+        app_id, path_prefix, verify_token, listen_port
+    );
+
+    let ctx = CachedContext::new(MyContext {
+        app_id,
+        app_secret,
+        verify_token,
+        db: 1,
+    });
+    let app = facebook_webhook_axum::router(
+        &path_prefix,
+        ctx,
+        Box::new(move |payload, ctx| {
+            Box::pin(async move {
+                let _ = ctx.inner().db;
+
+                println!("payload: {:?}", payload);
+
+                Ok(())
+            })
+        }),
+    );
+
+    axum::Server::bind(&([127, 0, 0, 1], listen_port).into())
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Clone)]
+struct MyContext {
+    app_id: u64,
+    app_secret: String,
+    verify_token: String,
+    db: i64,
+}
+#[async_trait]
+impl Context for MyContext {
+    async fn get_verify_token(&self, app_id: u64) -> Result<Secret, ContextError> {
+        if app_id == self.app_id {
+            Ok(Secret::new(self.verify_token.to_owned()))
+        } else {
+            Err("app_id mismatch".into())
+        }
+    }
+
+    async fn get_app_secret(&self, app_id: u64) -> Result<Secret, ContextError> {
+        if app_id == self.app_id {
+            Ok(Secret::new(self.app_secret.to_owned()).with_ttl(Duration::from_secs(300)))
+        } else {
+            Err("app_id mismatch".into())
+        }
+    }
+}
@@ -0,0 +1,233 @@
+pub use facebook_webhook;
+pub use facebook_webhook::context::{async_trait, CachedContext, Context, ContextError, Secret};
+
+use std::sync::Arc;
+
+use axum::{
+    body::HttpBody,
+    extract::{DefaultBodyLimit, FromRequest, FromRequestParts, Path, Query, State},
+    http::{HeaderValue, Request, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    BoxError, Router,
+};
+use bytes::Bytes;
+use facebook_webhook::{
+    event_notifications::{
+        self, PassBackCallbackFn, Payload, VerifyOptions, SIGNATURE_256_HEADER_NAME,
+        SIGNATURE_HEADER_NAME,
+    },
+    verification_requests::{self, Query as VerificationQuery},
+};
+
+pub fn router<C: Context + 'static>(
+    path_prefix: &str,
+    ctx: C,
+    callback: PassBackCallbackFn<'static, C>,
+) -> Router {
+    let callback = Arc::new(callback);
+
+    Router::new()
+        .route(
+            &format!("/{}/:app_id", path_prefix),
+            get(verification_requests_handler::<C>).post(
+                move |State(ctx): State<C>, verified_payload: VerifiedPayload| {
+                    let callback = callback.clone();
+
+                    async move {
+                        match callback(verified_payload.payload, ctx).await {
+                            Ok(_) => StatusCode::OK.into_response(),
+                            Err(err) => {
+                                (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+                            }
+                        }
+                    }
+                },
+            ),
+        )
+        .layer(DefaultBodyLimit::max(1024 * 32))
+        .with_state(ctx)
+}
+
+async fn verification_requests_handler<C: Context>(
+    State(ctx): State<C>,
+    Path(app_id): Path<u64>,
+    Query(query): Query<VerificationQuery>,
+) -> Response {
+    match ctx.get_verify_token(app_id).await {
+        Ok(verify_token) => {
+            let res = verification_requests::pass_back_with_query(query, &verify_token.value);
+
+            (res.status_code, res.body).into_response()
+        }
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+/// [`Payload`] verified by the app secrets of the [`Context`].
+///
+/// The state of the router must be the [`Context`], and `app_id` must be the only path param.
+#[derive(Debug, Clone)]
+pub struct VerifiedPayload {
+    pub app_id: u64,
+    pub payload: Payload,
+    /// Index in [`Context::get_app_secrets`] of the matched app secret.
+    pub app_secret_index: usize,
+}
+
+#[async_trait]
+impl<C, B> FromRequest<C, B> for VerifiedPayload
+where
+    C: Context,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request<B>, ctx: &C) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = req.into_parts();
+
+        let Path(app_id) = Path::<u64>::from_request_parts(&mut parts, ctx)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let signature_256_header_value = parts.headers.get(SIGNATURE_256_HEADER_NAME).cloned();
+        let signature_header_value = parts.headers.get(SIGNATURE_HEADER_NAME).cloned();
+
+        let request_body_bytes = Bytes::from_request(Request::from_parts(parts, body), ctx)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let app_secrets = ctx
+            .get_app_secrets(app_id)
+            .await
+            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response())?;
+
+        let app_secret_index = event_notifications::verify_payload_with_options(
+            &request_body_bytes[..],
+            &VerifyOptions::new(&app_secrets)
+                .with_signature_headers(
+                    signature_256_header_value
+                        .as_ref()
+                        .map(HeaderValue::as_bytes),
+                    signature_header_value.as_ref().map(HeaderValue::as_bytes),
+                )
+                .with_policy(ctx.get_signature_policy(app_id)),
+        )
+        .map_err(|err| (err.status_code(), err.to_string()).into_response())?;
+        ctx.on_app_secret_matched(app_id, app_secret_index);
+
+        let payload = serde_json::from_slice::<Payload>(&request_body_bytes[..])
+            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response())?;
+
+        Ok(Self {
+            app_id,
+            payload,
+            app_secret_index,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::body::Body;
+    use facebook_webhook::event_notifications::{sign_payload, SignatureAlgorithm};
+    use tower::ServiceExt as _;
+
+    #[derive(Debug, Clone)]
+    struct MyContext;
+
+    #[async_trait]
+    impl Context for MyContext {
+        async fn get_verify_token(&self, _app_id: u64) -> Result<Secret, ContextError> {
+            Ok(Secret::new("token"))
+        }
+
+        async fn get_app_secret(&self, _app_id: u64) -> Result<Secret, ContextError> {
+            Ok(Secret::new("key"))
+        }
+    }
+
+    fn app() -> Router {
+        router(
+            "fb_webhooks",
+            MyContext,
+            Box::new(|payload, _ctx| {
+                Box::pin(async move {
+                    match payload {
+                        Payload::Other { object, .. } if object == "foo" => Err("foo".into()),
+                        _ => Ok(()),
+                    }
+                })
+            }),
+        )
+    }
+
+    fn post(signature_256_header_value: Option<String>, body: &str) -> Request<Body> {
+        let mut builder = Request::post("/fb_webhooks/1");
+        if let Some(signature_256_header_value) = signature_256_header_value {
+            builder = builder.header(SIGNATURE_256_HEADER_NAME, signature_256_header_value);
+        }
+        builder.body(Body::from(body.to_owned())).unwrap()
+    }
+
+    fn sign(body: &str, app_secret: &str) -> Option<String> {
+        Some(sign_payload(body.as_bytes(), app_secret, SignatureAlgorithm::Sha256).unwrap())
+    }
+
+    async fn call(req: Request<Body>) -> (StatusCode, String) {
+        let res = app().oneshot(req).await.unwrap();
+        let status = res.status();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_router() {
+        //
+        let req = Request::get(
+            "/fb_webhooks/1?hub.mode=subscribe&hub.challenge=1158201444&hub.verify_token=token",
+        )
+        .body(Body::empty())
+        .unwrap();
+        assert_eq!(call(req).await, (StatusCode::OK, "1158201444".to_owned()));
+
+        let req = Request::get(
+            "/fb_webhooks/1?hub.mode=subscribe&hub.challenge=1158201444&hub.verify_token=other",
+        )
+        .body(Body::empty())
+        .unwrap();
+        assert_eq!(call(req).await.0, StatusCode::BAD_REQUEST);
+
+        //
+        let body = r#"{"object":"bar","entry":[]}"#;
+        assert_eq!(call(post(sign(body, "key"), body)).await.0, StatusCode::OK);
+
+        let body = r#"{"object":"foo","entry":[]}"#;
+        assert_eq!(
+            call(post(sign(body, "key"), body)).await,
+            (StatusCode::INTERNAL_SERVER_ERROR, "foo".to_owned())
+        );
+
+        assert_eq!(
+            call(post(None, body)).await,
+            (StatusCode::BAD_REQUEST, "SignatureHeaderMissing".to_owned())
+        );
+
+        assert_eq!(
+            call(post(sign(body, "other"), body)).await,
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "SignatureMismatch".to_owned()
+            )
+        );
+
+        let body = "foo";
+        assert_eq!(
+            call(post(sign(body, "key"), body)).await.0,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -0,0 +1,433 @@
+pub use facebook_webhook;
+pub use facebook_webhook::context::{async_trait, CachedContext, Context, ContextError, Secret};
+
+use core::{
+    convert::Infallible,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+use std::sync::Arc;
+
+use bytes::{BufMut as _, Bytes, BytesMut};
+use facebook_webhook::event_notifications::{
+    verify_payload_with_options, SignaturePolicy, VerifyOptions, SIGNATURE_256_HEADER_NAME,
+    SIGNATURE_HEADER_NAME,
+};
+use http::{request::Parts, HeaderMap, HeaderValue, Request, Response, StatusCode};
+use http_body::{Body as HttpBody, Full, LengthLimitError, Limited, SizeHint};
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pub const DEFAULT_BODY_LIMIT: usize = 1024 * 32;
+
+//
+#[async_trait]
+pub trait SecretResolver: Send + Sync {
+    /// Ordered candidates, e.g. the new and the old one while the app secret is being reset.
+    async fn get_app_secrets(&self, parts: &Parts) -> Result<Vec<Secret>, ContextError>;
+    fn on_app_secret_matched(&self, _parts: &Parts, _index: usize) {}
+    fn get_signature_policy(&self, _parts: &Parts) -> SignaturePolicy {
+        SignaturePolicy::default()
+    }
+}
+
+#[async_trait]
+impl SecretResolver for Secret {
+    async fn get_app_secrets(&self, _parts: &Parts) -> Result<Vec<Secret>, ContextError> {
+        Ok(vec![self.to_owned()])
+    }
+}
+
+#[async_trait]
+impl SecretResolver for Vec<Secret> {
+    async fn get_app_secrets(&self, _parts: &Parts) -> Result<Vec<Secret>, ContextError> {
+        Ok(self.to_owned())
+    }
+}
+
+/// Resolve by a [`Context`], the app id is the last segment of the path, e.g. `/fb_webhooks/{app_id}`.
+#[derive(Debug, Clone)]
+pub struct ContextResolver<C>(pub C);
+
+#[async_trait]
+impl<C> SecretResolver for ContextResolver<C>
+where
+    C: Context,
+{
+    async fn get_app_secrets(&self, parts: &Parts) -> Result<Vec<Secret>, ContextError> {
+        let app_id = app_id_from_path(parts.uri.path()).ok_or("app_id missing")?;
+        self.0.get_app_secrets(app_id).await
+    }
+
+    fn on_app_secret_matched(&self, parts: &Parts, index: usize) {
+        if let Some(app_id) = app_id_from_path(parts.uri.path()) {
+            self.0.on_app_secret_matched(app_id, index)
+        }
+    }
+
+    fn get_signature_policy(&self, parts: &Parts) -> SignaturePolicy {
+        app_id_from_path(parts.uri.path())
+            .map(|app_id| self.0.get_signature_policy(app_id))
+            .unwrap_or_default()
+    }
+}
+
+fn app_id_from_path(path: &str) -> Option<u64> {
+    path.trim_end_matches('/').rsplit('/').next()?.parse().ok()
+}
+
+/// Inserted into the extensions of the request passed downstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifiedSignature {
+    /// Index in [`SecretResolver::get_app_secrets`] of the matched app secret.
+    pub app_secret_index: usize,
+}
+
+//
+/// Buffer the body, verify `X-Hub-Signature-256` / `X-Hub-Signature`, then pass the request downstream with the buffered body.
+#[derive(Debug)]
+pub struct VerifySignatureLayer<R> {
+    resolver: Arc<R>,
+    body_limit: usize,
+}
+impl<R> VerifySignatureLayer<R> {
+    pub fn new(resolver: R) -> Self {
+        Self {
+            resolver: Arc::new(resolver),
+            body_limit: DEFAULT_BODY_LIMIT,
+        }
+    }
+
+    pub fn with_body_limit(mut self, body_limit: usize) -> Self {
+        self.body_limit = body_limit;
+        self
+    }
+}
+impl<R> Clone for VerifySignatureLayer<R> {
+    fn clone(&self) -> Self {
+        Self {
+            resolver: self.resolver.clone(),
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+impl<S, R> Layer<S> for VerifySignatureLayer<R> {
+    type Service = VerifySignature<S, R>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        VerifySignature {
+            inner,
+            resolver: self.resolver.clone(),
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VerifySignature<S, R> {
+    inner: S,
+    resolver: Arc<R>,
+    body_limit: usize,
+}
+impl<S, R> Clone for VerifySignature<S, R>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            resolver: self.resolver.clone(),
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+impl<S, R, ReqBody, ResBody> Service<Request<ReqBody>> for VerifySignature<S, R>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    R: SecretResolver + 'static,
+    ReqBody: HttpBody + From<Bytes> + Send + 'static,
+    ReqBody::Data: Send,
+    ReqBody::Error: Into<BoxError>,
+{
+    type Response = Response<ResponseBody<ResBody>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        // The ready one is self.inner, see https://docs.rs/tower/0.4/tower/trait.Service.html#be-careful-when-cloning-inner-services
+        let clone = self.inner.clone();
+        let mut inner = mem::replace(&mut self.inner, clone);
+        let resolver = self.resolver.clone();
+        let body_limit = self.body_limit;
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+
+            let request_body_bytes = match to_bytes(body, body_limit).await {
+                Ok(x) => x,
+                Err(err) => {
+                    let status_code = if err.downcast_ref::<LengthLimitError>().is_some() {
+                        StatusCode::PAYLOAD_TOO_LARGE
+                    } else {
+                        StatusCode::BAD_REQUEST
+                    };
+                    return Ok(rejection(status_code, err.to_string()));
+                }
+            };
+
+            let app_secrets = match resolver.get_app_secrets(&parts).await {
+                Ok(x) => x,
+                Err(err) => {
+                    return Ok(rejection(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        err.to_string(),
+                    ))
+                }
+            };
+
+            let app_secret_index = match verify_payload_with_options(
+                &request_body_bytes[..],
+                &VerifyOptions::new(&app_secrets)
+                    .with_signature_headers(
+                        parts
+                            .headers
+                            .get(SIGNATURE_256_HEADER_NAME)
+                            .map(HeaderValue::as_bytes),
+                        parts
+                            .headers
+                            .get(SIGNATURE_HEADER_NAME)
+                            .map(HeaderValue::as_bytes),
+                    )
+                    .with_policy(resolver.get_signature_policy(&parts)),
+            ) {
+                Ok(x) => x,
+                Err(err) => {
+                    return Ok(rejection(err.status_code(), err.to_string()));
+                }
+            };
+            resolver.on_app_secret_matched(&parts, app_secret_index);
+            parts
+                .extensions
+                .insert(VerifiedSignature { app_secret_index });
+
+            let res = inner
+                .call(Request::from_parts(
+                    parts,
+                    ReqBody::from(request_body_bytes),
+                ))
+                .await?;
+
+            Ok(res.map(|body| ResponseBody::Inner { body }))
+        })
+    }
+}
+
+async fn to_bytes<B>(body: B, limit: usize) -> Result<Bytes, BoxError>
+where
+    B: HttpBody,
+    B::Error: Into<BoxError>,
+{
+    let mut body = Box::pin(Limited::new(body, limit));
+
+    let mut buf = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        buf.put(chunk?);
+    }
+
+    Ok(buf.freeze())
+}
+
+fn rejection<B>(status_code: StatusCode, body: String) -> Response<ResponseBody<B>> {
+    let mut res = Response::new(ResponseBody::Rejection {
+        body: Full::from(body),
+    });
+    *res.status_mut() = status_code;
+    res
+}
+
+//
+pin_project! {
+    #[project = ResponseBodyProj]
+    pub enum ResponseBody<B> {
+        Inner {
+            #[pin]
+            body: B,
+        },
+        Rejection {
+            body: Full<Bytes>,
+        },
+    }
+}
+
+impl<B> HttpBody for ResponseBody<B>
+where
+    B: HttpBody<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        match self.project() {
+            ResponseBodyProj::Inner { body } => body.poll_data(cx),
+            ResponseBodyProj::Rejection { body } => Pin::new(body)
+                .poll_data(cx)
+                .map(|x| x.map(|x| x.map_err(|err: Infallible| match err {}))),
+        }
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        match self.project() {
+            ResponseBodyProj::Inner { body } => body.poll_trailers(cx),
+            ResponseBodyProj::Rejection { .. } => Poll::Ready(Ok(None)),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match self {
+            Self::Inner { body } => body.is_end_stream(),
+            Self::Rejection { body } => body.is_end_stream(),
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match self {
+            Self::Inner { body } => body.size_hint(),
+            Self::Rejection { body } => body.size_hint(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tower::{service_fn, ServiceExt as _};
+
+    async fn echo(req: Request<Full<Bytes>>) -> Result<Response<Full<Bytes>>, Infallible> {
+        let app_secret_index = req
+            .extensions()
+            .get::<VerifiedSignature>()
+            .unwrap()
+            .app_secret_index;
+        let body = to_bytes(req.into_body(), usize::MAX).await.unwrap();
+
+        Ok(Response::new(Full::from(format!(
+            "{} {}",
+            app_secret_index,
+            String::from_utf8(body.to_vec()).unwrap()
+        ))))
+    }
+
+    fn request(signature_256_header_value: Option<&str>, body: &str) -> Request<Full<Bytes>> {
+        let mut builder = Request::post("/fb_webhooks/1");
+        if let Some(signature_256_header_value) = signature_256_header_value {
+            builder = builder.header(SIGNATURE_256_HEADER_NAME, signature_256_header_value);
+        }
+        builder.body(Full::from(body.to_owned())).unwrap()
+    }
+
+    const SHA256: &str = "sha256=90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481";
+
+    #[tokio::test]
+    async fn test_verify_signature() {
+        let layer = VerifySignatureLayer::new(vec![Secret::new("other"), Secret::new("key")]);
+
+        //
+        let res = layer
+            .layer(service_fn(echo))
+            .oneshot(request(Some(SHA256), "value"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            to_bytes(res.into_body(), usize::MAX).await.unwrap(),
+            "1 value"
+        );
+
+        //
+        let res = layer
+            .layer(service_fn(echo))
+            .oneshot(request(None, "value"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        //
+        let res = layer
+            .layer(service_fn(echo))
+            .oneshot(request(Some(SHA256), "other value"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            to_bytes(res.into_body(), usize::MAX).await.unwrap(),
+            "SignatureMismatch"
+        );
+
+        //
+        let res = layer
+            .clone()
+            .with_body_limit(2)
+            .layer(service_fn(echo))
+            .oneshot(request(Some(SHA256), "value"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[derive(Clone)]
+    struct MyContext;
+
+    #[async_trait]
+    impl Context for MyContext {
+        async fn get_verify_token(&self, _app_id: u64) -> Result<Secret, ContextError> {
+            Ok(Secret::new("token"))
+        }
+
+        async fn get_app_secret(&self, app_id: u64) -> Result<Secret, ContextError> {
+            if app_id == 1 {
+                Ok(Secret::new("key"))
+            } else {
+                Err("app_id mismatch".into())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_context_resolver() {
+        let layer = VerifySignatureLayer::new(ContextResolver(MyContext));
+
+        let res = layer
+            .layer(service_fn(echo))
+            .oneshot(request(Some(SHA256), "value"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let mut req = request(Some(SHA256), "value");
+        *req.uri_mut() = "/fb_webhooks/2".parse().unwrap();
+        let res = layer.layer(service_fn(echo)).oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        assert_eq!(app_id_from_path("/fb_webhooks/1/"), Some(1));
+        assert_eq!(app_id_from_path("/fb_webhooks"), None);
+    }
+}
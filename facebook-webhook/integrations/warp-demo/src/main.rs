@@ -8,9 +8,9 @@ sudo systemctl reload nginx
 cargo run -p facebook-webhook-warp-demo -- 202000000000000 YOUR_APP_SECRET
 */
 
-use std::env;
+use std::{env, time::Duration};
 
-use facebook_webhook_warp::Context;
+use facebook_webhook_warp::{async_trait, CachedContext, Context, ContextError, Secret};
 use passwords::PasswordGenerator;
 use warp::Filter as _;
 
@@ -60,18 +60,18 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
         app_id, path_prefix, verify_token, listen_port
     );
 
-    let ctx = MyContext {
+    let ctx = CachedContext::new(MyContext {
         app_id,
         app_secret,
         verify_token,
         db: 1,
-    };
+    });
     let api = facebook_webhook_warp::handle(
         path_prefix,
         ctx,
         Box::new(move |payload, ctx| {
             Box::pin(async move {
-                let _ = ctx.db;
+                let _ = ctx.inner().db;
 
                 println!("payload: {:?}", payload);
 
@@ -94,24 +94,19 @@ struct MyContext {
     verify_token: String,
     db: i64,
 }
+#[async_trait]
 impl Context for MyContext {
-    fn get_verify_token(
-        &self,
-        app_id: u64,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    async fn get_verify_token(&self, app_id: u64) -> Result<Secret, ContextError> {
         if app_id == self.app_id {
-            Ok(self.verify_token.to_owned())
+            Ok(Secret::new(self.verify_token.to_owned()))
         } else {
             Err("app_id mismatch".into())
         }
     }
 
-    fn get_app_secret(
-        &self,
-        app_id: u64,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    async fn get_app_secret(&self, app_id: u64) -> Result<Secret, ContextError> {
         if app_id == self.app_id {
-            Ok(self.app_secret.to_owned())
+            Ok(Secret::new(self.app_secret.to_owned()).with_ttl(Duration::from_secs(300)))
         } else {
             Err("app_id mismatch".into())
         }
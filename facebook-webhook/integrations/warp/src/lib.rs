@@ -1,11 +1,21 @@
 pub use facebook_webhook;
+pub use facebook_webhook::context::{async_trait, CachedContext, Context, ContextError, Secret};
+pub use facebook_webhook::dedup::{
+    Dedup, DedupStatus, DedupStore, FingerprintStrategy, InMemoryDedupStore,
+};
+#[cfg(feature = "with-queue")]
+pub use facebook_webhook::queue::{Backpressure, WorkQueue, WorkQueueConfig, WorkQueueHandle};
+#[cfg(feature = "with-spool")]
+pub use facebook_webhook::spool::{FsyncPolicy, Spool, SpoolConfig};
 
 use core::convert::Infallible;
 use std::sync::Arc;
 
 use bytes::Bytes;
 use facebook_webhook::{
-    event_notifications::{self, PassBackCallbackFn, SIGNATURE_HEADER_NAME},
+    event_notifications::{
+        self, PassBackCallbackFn, VerifyOptions, SIGNATURE_256_HEADER_NAME, SIGNATURE_HEADER_NAME,
+    },
     verification_requests::{self, Query},
 };
 use warp::{
@@ -14,30 +24,82 @@ use warp::{
     Filter,
 };
 
-pub trait Context: Send + Sync + Clone {
-    fn get_verify_token(
-        &self,
-        app_id: u64,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>>;
-    fn get_app_secret(
-        &self,
-        app_id: u64,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>>;
+pub fn handle<C: Context + 'static>(
+    path_prefix: String,
+    ctx: C,
+    callback: PassBackCallbackFn<'static, C>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    verification_requests_filter(path_prefix.clone(), ctx.clone()).or(event_notifications_filter(
+        path_prefix,
+        ctx,
+        Processor::Callback(Arc::new(callback)),
+    ))
 }
 
-pub fn handle<C: Context>(
+/// Same as [`handle`], but the duplicates are acked without calling `callback`.
+pub fn handle_with_dedup<C: Context + 'static>(
     path_prefix: String,
     ctx: C,
     callback: PassBackCallbackFn<'static, C>,
+    dedup: Dedup,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    verification_requests_filter(path_prefix.clone(), ctx.clone()).or(event_notifications_filter(
+        path_prefix,
+        ctx,
+        Processor::Dedup(Arc::new(callback), dedup),
+    ))
+}
+
+/// Respond right after verifying and parsing, the payload is processed by the `queue`.
+#[cfg(feature = "with-queue")]
+pub fn handle_with_queue<C: Context + 'static>(
+    path_prefix: String,
+    ctx: C,
+    queue: WorkQueue<C>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
     verification_requests_filter(path_prefix.clone(), ctx.clone()).or(event_notifications_filter(
         path_prefix,
         ctx,
-        Arc::new(callback),
+        Processor::Queue(queue),
     ))
 }
 
-fn verification_requests_filter<C: Context>(
+/// Respond once the delivery is appended to the `spool`, process it by `Spool::replay`.
+#[cfg(feature = "with-spool")]
+pub fn handle_with_spool<C: Context + 'static>(
+    path_prefix: String,
+    ctx: C,
+    spool: Spool,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    verification_requests_filter(path_prefix.clone(), ctx.clone()).or(event_notifications_filter(
+        path_prefix,
+        ctx,
+        Processor::Spool(spool),
+    ))
+}
+
+enum Processor<C> {
+    Callback(Arc<PassBackCallbackFn<'static, C>>),
+    Dedup(Arc<PassBackCallbackFn<'static, C>>, Dedup),
+    #[cfg(feature = "with-queue")]
+    Queue(WorkQueue<C>),
+    #[cfg(feature = "with-spool")]
+    Spool(Spool),
+}
+impl<C> Clone for Processor<C> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Callback(callback) => Self::Callback(callback.clone()),
+            Self::Dedup(callback, dedup) => Self::Dedup(callback.clone(), dedup.clone()),
+            #[cfg(feature = "with-queue")]
+            Self::Queue(queue) => Self::Queue(queue.clone()),
+            #[cfg(feature = "with-spool")]
+            Self::Spool(spool) => Self::Spool(spool.clone()),
+        }
+    }
+}
+
+fn verification_requests_filter<C: Context + 'static>(
     path_prefix: String,
     ctx: C,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
@@ -51,10 +113,12 @@ fn verification_requests_filter<C: Context>(
 
             async move {
                 let part: Result<Result<Response<Body>, warp::http::Error>, Infallible> = {
-                    match ctx.get_verify_token(app_id) {
+                    match ctx.get_verify_token(app_id).await {
                         Ok(verify_token) => {
-                            let res =
-                                verification_requests::pass_back_with_query(query, &verify_token);
+                            let res = verification_requests::pass_back_with_query(
+                                query,
+                                &verify_token.value,
+                            );
 
                             Ok(Response::builder()
                                 .status(res.status_code)
@@ -70,34 +134,79 @@ fn verification_requests_filter<C: Context>(
         })
 }
 
-fn event_notifications_filter<C: Context>(
+fn event_notifications_filter<C: Context + 'static>(
     path_prefix: String,
     ctx: C,
-    callback: Arc<PassBackCallbackFn<'static, C>>,
+    processor: Processor<C>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
     warp::path(path_prefix)
         .and(warp::path::param::<u64>())
         .and(warp::path::end())
         .and(warp::post())
-        .and(warp::header::<String>(SIGNATURE_HEADER_NAME))
+        .and(warp::header::optional::<String>(SIGNATURE_256_HEADER_NAME))
+        .and(warp::header::optional::<String>(SIGNATURE_HEADER_NAME))
         .and(warp::body::content_length_limit(1024 * 32).and(warp::body::bytes()))
         .and_then(
-            move |app_id, signature_header_value: String, request_body_bytes: Bytes| {
+            move |app_id,
+                  signature_256_header_value: Option<String>,
+                  signature_header_value: Option<String>,
+                  request_body_bytes: Bytes| {
                 let ctx = ctx.clone();
-                let callback = callback.clone();
+                let processor = processor.clone();
 
                 async move {
                     let part: Result<Result<Response<Body>, warp::http::Error>, Infallible> = {
-                        match ctx.get_app_secret(app_id) {
-                            Ok(app_secret) => {
-                                let res = event_notifications::pass_back(
-                                    signature_header_value.as_bytes(),
-                                    &request_body_bytes[..],
-                                    &app_secret,
-                                    ctx,
-                                    callback,
-                                )
-                                .await;
+                        match ctx.get_app_secrets(app_id).await {
+                            Ok(app_secrets) => {
+                                let options = VerifyOptions::new(&app_secrets)
+                                    .with_signature_headers(
+                                        signature_256_header_value.as_deref().map(str::as_bytes),
+                                        signature_header_value.as_deref().map(str::as_bytes),
+                                    )
+                                    .with_policy(ctx.get_signature_policy(app_id));
+                                let res = match processor {
+                                    Processor::Callback(callback) => {
+                                        event_notifications::pass_back(
+                                            &request_body_bytes[..],
+                                            &options,
+                                            ctx.clone(),
+                                            callback,
+                                        )
+                                        .await
+                                    }
+                                    Processor::Dedup(callback, dedup) => {
+                                        event_notifications::pass_back_with_dedup(
+                                            &request_body_bytes[..],
+                                            &options,
+                                            ctx.clone(),
+                                            callback,
+                                            &dedup,
+                                        )
+                                        .await
+                                    }
+                                    #[cfg(feature = "with-queue")]
+                                    Processor::Queue(queue) => {
+                                        event_notifications::pass_back_with_queue(
+                                            &request_body_bytes[..],
+                                            &options,
+                                            ctx.clone(),
+                                            &queue,
+                                        )
+                                        .await
+                                    }
+                                    #[cfg(feature = "with-spool")]
+                                    Processor::Spool(spool) => {
+                                        event_notifications::pass_back_with_spool(
+                                            &request_body_bytes[..],
+                                            &options,
+                                            &spool,
+                                        )
+                                        .await
+                                    }
+                                };
+                                if let Some(index) = res.app_secret_index {
+                                    ctx.on_app_secret_matched(app_id, index);
+                                }
 
                                 Ok(Response::builder()
                                     .status(res.status_code)
@@ -0,0 +1,119 @@
+//! Per app id secrets for the framework integrations.
+
+pub use facebook_secret::{async_trait, CachedContext, ContextError, Secret};
+
+use facebook_secret::SecretKind;
+
+use crate::event_notifications::SignaturePolicy;
+
+#[async_trait]
+pub trait Context: Send + Sync + Clone {
+    async fn get_verify_token(&self, app_id: u64) -> Result<Secret, ContextError>;
+    async fn get_app_secret(&self, app_id: u64) -> Result<Secret, ContextError>;
+    /// Ordered candidates, e.g. the new and the old one while the app secret is being reset.
+    async fn get_app_secrets(&self, app_id: u64) -> Result<Vec<Secret>, ContextError> {
+        self.get_app_secret(app_id).await.map(|x| vec![x])
+    }
+    /// Called with the index in [`Context::get_app_secrets`] of the secret that verified the payload.
+    fn on_app_secret_matched(&self, _app_id: u64, _index: usize) {}
+    fn get_signature_policy(&self, _app_id: u64) -> SignaturePolicy {
+        SignaturePolicy::default()
+    }
+}
+
+#[async_trait]
+impl<C> Context for CachedContext<C>
+where
+    C: Context,
+{
+    async fn get_verify_token(&self, app_id: u64) -> Result<Secret, ContextError> {
+        self.get_or_load(SecretKind::VerifyToken, app_id, || async {
+            self.inner().get_verify_token(app_id).await.map(|x| vec![x])
+        })
+        .await
+        .map(|mut x| x.remove(0))
+    }
+
+    async fn get_app_secret(&self, app_id: u64) -> Result<Secret, ContextError> {
+        self.get_or_load(SecretKind::AppSecret, app_id, || async {
+            self.inner().get_app_secret(app_id).await.map(|x| vec![x])
+        })
+        .await
+        .map(|mut x| x.remove(0))
+    }
+
+    async fn get_app_secrets(&self, app_id: u64) -> Result<Vec<Secret>, ContextError> {
+        self.get_or_load(SecretKind::AppSecrets, app_id, || {
+            self.inner().get_app_secrets(app_id)
+        })
+        .await
+    }
+
+    fn on_app_secret_matched(&self, app_id: u64, index: usize) {
+        self.inner().on_app_secret_matched(app_id, index)
+    }
+
+    fn get_signature_policy(&self, app_id: u64) -> SignaturePolicy {
+        self.inner().get_signature_policy(app_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct MyContext {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Context for MyContext {
+        async fn get_verify_token(&self, _app_id: u64) -> Result<Secret, ContextError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Secret::new("token"))
+        }
+
+        async fn get_app_secret(&self, app_id: u64) -> Result<Secret, ContextError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if app_id == 0 {
+                return Err("app_id mismatch".into());
+            }
+            Ok(Secret::new("secret").with_ttl(Duration::from_secs(60)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_context() {
+        let ctx = CachedContext::new(MyContext::default());
+
+        assert_eq!(ctx.get_app_secret(1).await.unwrap().value, "secret");
+        assert_eq!(ctx.get_app_secret(1).await.unwrap().value, "secret");
+        assert_eq!(ctx.inner().calls.load(Ordering::SeqCst), 1);
+
+        ctx.invalidate(1);
+        ctx.get_app_secret(1).await.unwrap();
+        assert_eq!(ctx.inner().calls.load(Ordering::SeqCst), 2);
+
+        // without ttl
+        ctx.get_verify_token(1).await.unwrap();
+        ctx.get_verify_token(1).await.unwrap();
+        assert_eq!(ctx.inner().calls.load(Ordering::SeqCst), 4);
+
+        // error
+        assert!(ctx.get_app_secret(0).await.is_err());
+        assert!(ctx.get_app_secret(0).await.is_err());
+        assert_eq!(ctx.inner().calls.load(Ordering::SeqCst), 6);
+
+        // candidates
+        assert_eq!(ctx.get_app_secrets(1).await.unwrap().len(), 1);
+        assert_eq!(ctx.get_app_secrets(1).await.unwrap().len(), 1);
+        assert_eq!(ctx.inner().calls.load(Ordering::SeqCst), 7);
+    }
+}
@@ -0,0 +1,268 @@
+//! Ack the redeliveries without calling the callback again.
+//!
+//! Facebook redelivers the same payload when the response is slow or not `200`.
+
+use core::time::Duration;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use serde_json::Value;
+use sha2::{Digest as _, Sha256};
+
+use crate::context::{async_trait, ContextError};
+
+pub type Fingerprint = [u8; 32];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FingerprintStrategy {
+    /// SHA-256 of the body, only the byte-identical redeliveries are duplicates.
+    #[default]
+    BodyHash,
+    /// SHA-256 of the `object`, every entry's `id` and `time`, every change's `field` and every messaging event.
+    ///
+    /// Survives the reordered keys, but the changes of the same field in the same entry are the same.
+    EntryKey,
+}
+
+pub fn fingerprint(
+    request_body_bytes: &[u8],
+    strategy: FingerprintStrategy,
+) -> Result<Fingerprint, serde_json::Error> {
+    let mut hasher = Sha256::new();
+
+    match strategy {
+        FingerprintStrategy::BodyHash => hasher.update(request_body_bytes),
+        FingerprintStrategy::EntryKey => {
+            let value = serde_json::from_slice::<Value>(request_body_bytes)?;
+
+            // serde_json::Map is sorted, so the serialization is canonical.
+            let mut key = vec![value.get("object").cloned().unwrap_or_default()];
+            for entry in value
+                .get("entry")
+                .and_then(|x| x.as_array())
+                .into_iter()
+                .flatten()
+            {
+                key.push(entry.get("id").cloned().unwrap_or_default());
+                key.push(entry.get("time").cloned().unwrap_or_default());
+                for change in entry
+                    .get("changes")
+                    .and_then(|x| x.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    key.push(change.get("field").cloned().unwrap_or_default());
+                }
+                for event in entry
+                    .get("messaging")
+                    .and_then(|x| x.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    key.push(event.clone());
+                }
+            }
+
+            hasher.update(serde_json::to_vec(&key)?);
+        }
+    }
+
+    Ok(hasher.finalize().into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupStatus {
+    /// Not seen, it is in flight now.
+    New,
+    /// Seen, but the callback isn't done yet.
+    InFlight,
+    /// Seen, and the callback succeeded.
+    Done,
+}
+
+#[async_trait]
+pub trait DedupStore: Send + Sync {
+    /// Mark it as in flight when it is [`DedupStatus::New`], otherwise return the current status.
+    async fn insert(&self, fingerprint: Fingerprint) -> Result<DedupStatus, ContextError>;
+
+    /// Called when the callback succeeds.
+    async fn mark_done(&self, fingerprint: &Fingerprint) -> Result<(), ContextError>;
+
+    /// Forget it, so the redelivery is processed. Called when the callback fails.
+    async fn remove(&self, fingerprint: &Fingerprint) -> Result<(), ContextError>;
+}
+
+#[derive(Clone)]
+pub struct Dedup {
+    pub store: Arc<dyn DedupStore>,
+    pub strategy: FingerprintStrategy,
+}
+impl Dedup {
+    pub fn new(store: impl DedupStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            strategy: FingerprintStrategy::default(),
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: FingerprintStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+}
+impl core::fmt::Debug for Dedup {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Dedup")
+            .field("strategy", &self.strategy)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keep up to `capacity` fingerprints, each for `ttl` since it is last seen.
+#[derive(Debug)]
+pub struct InMemoryDedupStore {
+    capacity: usize,
+    ttl: Duration,
+    inner: Mutex<InMemoryDedupStoreInner>,
+}
+
+#[derive(Debug, Default)]
+struct InMemoryDedupStoreInner {
+    seen: HashMap<Fingerprint, (Instant, u64, DedupStatus)>,
+    /// Sequence of the last seen, the least recently seen first.
+    order: BTreeMap<u64, Fingerprint>,
+    seq: u64,
+}
+
+impl InMemoryDedupStore {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ttl,
+            inner: Default::default(),
+        }
+    }
+}
+impl Default for InMemoryDedupStore {
+    /// Facebook retries for up to 36 hours.
+    fn default() -> Self {
+        Self::new(10_000, Duration::from_secs(36 * 60 * 60))
+    }
+}
+
+#[async_trait]
+impl DedupStore for InMemoryDedupStore {
+    async fn insert(&self, fingerprint: Fingerprint) -> Result<DedupStatus, ContextError> {
+        let mut inner = self.inner.lock().expect("dedup store lock poisoned");
+        let now = Instant::now();
+
+        while let Some((seq, fingerprint)) = inner.order.first_key_value().map(|(k, v)| (*k, *v)) {
+            let expired = inner
+                .seen
+                .get(&fingerprint)
+                .map(|(seen_at, _, _)| now.duration_since(*seen_at) >= self.ttl)
+                .unwrap_or(true);
+            if !expired {
+                break;
+            }
+            inner.order.remove(&seq);
+            inner.seen.remove(&fingerprint);
+        }
+
+        inner.seq += 1;
+        let seq = inner.seq;
+        let status = match inner.seen.get(&fingerprint) {
+            Some((_, prev_seq, status)) => {
+                let (prev_seq, status) = (*prev_seq, *status);
+                inner.order.remove(&prev_seq);
+                status
+            }
+            None => DedupStatus::New,
+        };
+        let next_status = match status {
+            DedupStatus::New => DedupStatus::InFlight,
+            x => x,
+        };
+        inner.seen.insert(fingerprint, (now, seq, next_status));
+        inner.order.insert(seq, fingerprint);
+
+        while inner.seen.len() > self.capacity {
+            match inner.order.pop_first() {
+                Some((_, fingerprint)) => {
+                    inner.seen.remove(&fingerprint);
+                }
+                None => break,
+            }
+        }
+
+        Ok(status)
+    }
+
+    async fn mark_done(&self, fingerprint: &Fingerprint) -> Result<(), ContextError> {
+        let mut inner = self.inner.lock().expect("dedup store lock poisoned");
+        if let Some((_, _, status)) = inner.seen.get_mut(fingerprint) {
+            *status = DedupStatus::Done;
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, fingerprint: &Fingerprint) -> Result<(), ContextError> {
+        let mut inner = self.inner.lock().expect("dedup store lock poisoned");
+        if let Some((_, seq, _)) = inner.seen.remove(fingerprint) {
+            inner.order.remove(&seq);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let a = br#"{"object":"page","entry":[{"id":"1","time":1,"changes":[{"field":"feed","value":{"a":1}}]}]}"#;
+        let b = br#"{"entry":[{"changes":[{"value":{"a":2},"field":"feed"}],"time":1,"id":"1"}],"object":"page"}"#;
+        let c = br#"{"object":"page","entry":[{"id":"1","time":2,"changes":[{"field":"feed","value":{"a":1}}]}]}"#;
+
+        let body_hash = |x: &[u8]| fingerprint(x, FingerprintStrategy::BodyHash).unwrap();
+        assert_eq!(body_hash(a), body_hash(a));
+        assert_ne!(body_hash(a), body_hash(b));
+
+        let entry_key = |x: &[u8]| fingerprint(x, FingerprintStrategy::EntryKey).unwrap();
+        assert_eq!(entry_key(a), entry_key(b));
+        assert_ne!(entry_key(a), entry_key(c));
+
+        assert!(fingerprint(b"{", FingerprintStrategy::EntryKey).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_dedup_store() {
+        use DedupStatus::{Done, InFlight, New};
+
+        let store = InMemoryDedupStore::new(2, Duration::from_secs(60));
+
+        assert_eq!(store.insert([1; 32]).await.unwrap(), New);
+        assert_eq!(store.insert([1; 32]).await.unwrap(), InFlight);
+        store.mark_done(&[1; 32]).await.unwrap();
+        assert_eq!(store.insert([1; 32]).await.unwrap(), Done);
+        assert_eq!(store.insert([2; 32]).await.unwrap(), New);
+        // [1] is seen more recently, so [2] is evicted.
+        assert_eq!(store.insert([1; 32]).await.unwrap(), Done);
+        assert_eq!(store.insert([3; 32]).await.unwrap(), New);
+        assert_eq!(store.insert([2; 32]).await.unwrap(), New);
+        assert_eq!(store.insert([2; 32]).await.unwrap(), InFlight);
+
+        store.remove(&[2; 32]).await.unwrap();
+        assert_eq!(store.insert([2; 32]).await.unwrap(), New);
+
+        // ttl
+        let store = InMemoryDedupStore::new(10, Duration::from_millis(10));
+        assert_eq!(store.insert([1; 32]).await.unwrap(), New);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(store.insert([1; 32]).await.unwrap(), New);
+    }
+}
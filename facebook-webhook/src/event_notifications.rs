@@ -1,6 +1,7 @@
 //! [Official doc](https://developers.facebook.com/docs/graph-api/webhooks/getting-started#event-notifications)
 
 use core::{
+    fmt,
     future::Future,
     pin::Pin,
     str::{self, FromStr},
@@ -10,19 +11,35 @@ use std::sync::Arc;
 use chrono::{serde::ts_seconds, DateTime, Utc};
 use hmac::{Hmac, Mac as _};
 use http::StatusCode;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_aux::field_attributes::deserialize_number_from_string;
+use serde_json::Value;
 use sha1::Sha1;
+use sha2::Sha256;
 
-use crate::topics::{instagram::Instagram, permissions::Permissions};
+#[cfg(feature = "with-queue")]
+use crate::queue::WorkQueue;
+#[cfg(feature = "with-spool")]
+use crate::spool::{self, Spool};
+use crate::{
+    dedup::{self, Dedup, DedupStatus},
+    serde_ext::{deserialize_timestamp, serialize_timestamp, serialize_to_string},
+    topics::{
+        instagram::Instagram, instagram_messaging::Messaging as InstagramMessaging,
+        messenger::Messaging, page::Page, permissions::Permissions, whatsapp::Whatsapp,
+    },
+};
 
 type HmacSha1 = Hmac<Sha1>;
+type HmacSha256 = Hmac<Sha256>;
 
 pub const SIGNATURE_HEADER_NAME: &str = "X-Hub-Signature";
+pub const SIGNATURE_256_HEADER_NAME: &str = "X-Hub-Signature-256";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Signature {
     Sha1(String),
+    Sha256(String),
 }
 
 impl FromStr for Signature {
@@ -45,16 +62,149 @@ impl FromStr for Signature {
 
                 Ok(Self::Sha1(value.to_owned()))
             }
+            "sha256" => {
+                if value.len() != 64 {
+                    return Err("value length invalid");
+                }
+
+                Ok(Self::Sha256(value.to_owned()))
+            }
             _ => Err("algorithm unknown"),
         }
     }
 }
 
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sha1(value) => write!(f, "sha1={value}"),
+            Self::Sha256(value) => write!(f, "sha256={value}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignatureAlgorithm {
+    Sha1,
+    #[default]
+    Sha256,
+}
+impl SignatureAlgorithm {
+    pub fn header_name(&self) -> &'static str {
+        match self {
+            Self::Sha1 => SIGNATURE_HEADER_NAME,
+            Self::Sha256 => SIGNATURE_256_HEADER_NAME,
+        }
+    }
+}
+
+/// The value of the [`SignatureAlgorithm::header_name`] header, signed the way Facebook does.
+pub fn sign_payload(
+    request_body_bytes: &[u8],
+    app_secret: &str,
+    algorithm: SignatureAlgorithm,
+) -> Result<String, SignPayloadError> {
+    let signature = match algorithm {
+        SignatureAlgorithm::Sha1 => Signature::Sha1(hex::encode(
+            hmac_sha1_payload(request_body_bytes, app_secret)
+                .map_err(|_| SignPayloadError::CalculateSignatureFailed)?
+                .finalize()
+                .into_bytes(),
+        )),
+        SignatureAlgorithm::Sha256 => Signature::Sha256(hex::encode(
+            hmac_sha256_payload(request_body_bytes, app_secret)
+                .map_err(|_| SignPayloadError::CalculateSignatureFailed)?
+                .finalize()
+                .into_bytes(),
+        )),
+    };
+
+    Ok(signature.to_string())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SignPayloadError {
+    #[error("SerializeFailed {0}")]
+    SerializeFailed(serde_json::Error),
+    #[error("CalculateSignatureFailed")]
+    CalculateSignatureFailed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignaturePolicy {
+    /// Accept `X-Hub-Signature-256`, fall back to `X-Hub-Signature` when it is missing.
+    #[default]
+    AllowSha1,
+    /// Reject deliveries that are only signed with SHA-1.
+    RequireSha256,
+}
+
+/// The signature headers of a delivery, and the app secrets and [`SignaturePolicy`] to verify them with.
+///
+/// e.g. `VerifyOptions::new(&[new_app_secret, old_app_secret])` while the app secret is being reset.
+pub struct VerifyOptions<'a, S> {
+    signature_256_header_value: Option<&'a [u8]>,
+    signature_header_value: Option<&'a [u8]>,
+    app_secrets: &'a [S],
+    policy: SignaturePolicy,
+}
+impl<S> fmt::Debug for VerifyOptions<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VerifyOptions")
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+impl<'a, S: AsRef<str>> VerifyOptions<'a, S> {
+    pub fn new(app_secrets: &'a [S]) -> Self {
+        Self {
+            signature_256_header_value: None,
+            signature_header_value: None,
+            app_secrets,
+            policy: SignaturePolicy::default(),
+        }
+    }
+
+    /// Values of `X-Hub-Signature-256` and `X-Hub-Signature`, the first is preferred when both are present.
+    pub fn with_signature_headers(
+        mut self,
+        signature_256_header_value: Option<&'a [u8]>,
+        signature_header_value: Option<&'a [u8]>,
+    ) -> Self {
+        self.signature_256_header_value = signature_256_header_value;
+        self.signature_header_value = signature_header_value;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: SignaturePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
 pub fn verify_payload(
     signature_header_value: &[u8],
     request_body_bytes: &[u8],
     app_secret: &str,
 ) -> Result<(), VerifyPayloadError> {
+    verify_payload_with_options(
+        request_body_bytes,
+        &VerifyOptions::new(&[app_secret])
+            .with_signature_headers(None, Some(signature_header_value)),
+    )
+    .map(|_| ())
+}
+
+/// Same as [`verify_payload`], returns the index of the first matched app secret.
+pub fn verify_payload_with_options<S: AsRef<str>>(
+    request_body_bytes: &[u8],
+    options: &VerifyOptions<'_, S>,
+) -> Result<usize, VerifyPayloadError> {
+    let signature_header_value = options
+        .signature_256_header_value
+        .or(options.signature_header_value)
+        .ok_or(VerifyPayloadError::SignatureHeaderMissing)?;
+
     let signature_header_value = str::from_utf8(signature_header_value)
         .map_err(|_| VerifyPayloadError::SignatureHeaderValueInvalid("header invalid"))?;
 
@@ -62,76 +212,272 @@ pub fn verify_payload(
         .parse()
         .map_err(VerifyPayloadError::SignatureHeaderValueInvalid)?;
 
-    match signature {
-        Signature::Sha1(expected_sig) => {
-            let sig = hmac_sha1_payload(request_body_bytes, app_secret)
-                .map_err(|_| VerifyPayloadError::CalculateSignatureFailed)?;
+    verify_payload_with_signature(signature, request_body_bytes, options)
+}
 
-            if expected_sig.to_ascii_lowercase() != sig {
-                return Err(VerifyPayloadError::SignatureMismatch);
+/// Same as [`verify_payload_with_options`], the signature headers of `options` are ignored.
+pub fn verify_payload_with_signature<S: AsRef<str>>(
+    signature: Signature,
+    request_body_bytes: &[u8],
+    options: &VerifyOptions<'_, S>,
+) -> Result<usize, VerifyPayloadError> {
+    let expected_sig = match &signature {
+        Signature::Sha1(expected_sig) => {
+            if options.policy == SignaturePolicy::RequireSha256 {
+                return Err(VerifyPayloadError::SignatureAlgorithmNotAllowed);
             }
+            expected_sig
+        }
+        Signature::Sha256(expected_sig) => expected_sig,
+    };
+    let expected_sig = hex::decode(expected_sig)
+        .map_err(|_| VerifyPayloadError::SignatureHeaderValueInvalid("value invalid"))?;
+
+    for (i, app_secret) in options.app_secrets.iter().enumerate() {
+        let matched = match signature {
+            Signature::Sha1(_) => hmac_sha1_payload(request_body_bytes, app_secret.as_ref())
+                .map_err(|_| VerifyPayloadError::CalculateSignatureFailed)?
+                .verify_slice(&expected_sig)
+                .is_ok(),
+            Signature::Sha256(_) => hmac_sha256_payload(request_body_bytes, app_secret.as_ref())
+                .map_err(|_| VerifyPayloadError::CalculateSignatureFailed)?
+                .verify_slice(&expected_sig)
+                .is_ok(),
+        };
+        if matched {
+            return Ok(i);
         }
     }
 
-    Ok(())
+    Err(VerifyPayloadError::SignatureMismatch)
 }
 
 // $ echo -n "value" | openssl sha1 -hmac "key"
 // (stdin)= 57443a4c052350a44638835d64fd66822f813319
-fn hmac_sha1_payload(request_body_bytes: &[u8], app_secret: &str) -> Result<String, String> {
+fn hmac_sha1_payload(request_body_bytes: &[u8], app_secret: &str) -> Result<HmacSha1, String> {
     let mut hmac =
         HmacSha1::new_from_slice(app_secret.as_bytes()).map_err(|err| err.to_string())?;
     hmac.update(request_body_bytes);
-    let hmac_result = hmac.finalize().into_bytes();
-    let sig = hex::encode(hmac_result).to_ascii_lowercase();
 
-    Ok(sig)
+    Ok(hmac)
+}
+
+// $ echo -n "value" | openssl sha256 -hmac "key"
+// (stdin)= 90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481
+fn hmac_sha256_payload(request_body_bytes: &[u8], app_secret: &str) -> Result<HmacSha256, String> {
+    let mut hmac =
+        HmacSha256::new_from_slice(app_secret.as_bytes()).map_err(|err| err.to_string())?;
+    hmac.update(request_body_bytes);
+
+    Ok(hmac)
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum VerifyPayloadError {
+    #[error("SignatureHeaderMissing")]
+    SignatureHeaderMissing,
     #[error("SignatureHeaderValueInvalid")]
     SignatureHeaderValueInvalid(&'static str),
     #[error("CalculateSignatureFailed")]
     CalculateSignatureFailed,
+    #[error("SignatureAlgorithmNotAllowed")]
+    SignatureAlgorithmNotAllowed,
     #[error("SignatureMismatch")]
     SignatureMismatch,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+impl VerifyPayloadError {
+    /// `400` when the signature header is missing, invalid or not allowed, otherwise `500`.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::SignatureHeaderMissing
+            | Self::SignatureHeaderValueInvalid(_)
+            | Self::SignatureAlgorithmNotAllowed => StatusCode::BAD_REQUEST,
+            Self::CalculateSignatureFailed | Self::SignatureMismatch => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(tag = "object", content = "entry", rename_all = "snake_case")]
 pub enum Payload {
     Instagram(Vec<InstagramObjectEntry>),
+    Page(Vec<PageObjectEntry>),
     Permissions(Vec<PermissionsObjectEntry>),
+    WhatsappBusinessAccount(Vec<WhatsappBusinessAccountObjectEntry>),
+    /// Unknown object, or entries this crate can't parse yet.
+    #[serde(untagged)]
+    Other {
+        object: String,
+        entry: Vec<Value>,
+    },
 }
 
-#[derive(Deserialize, Debug, Clone)]
+impl Payload {
+    /// The request body and the signature header value, e.g. to drive [`pass_back`] in tests.
+    pub fn to_signed(
+        &self,
+        app_secret: &str,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<(Vec<u8>, String), SignPayloadError> {
+        let body = serde_json::to_vec(self).map_err(SignPayloadError::SerializeFailed)?;
+        let signature = sign_payload(&body, app_secret, algorithm)?;
+        Ok((body, signature))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct InstagramObjectEntry {
     /// id == [IG User id](https://developers.facebook.com/docs/instagram-api/reference/ig-user)
-    #[serde(deserialize_with = "deserialize_number_from_string")]
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
     pub id: u64,
-    #[serde(with = "ts_seconds")]
+    /// Seconds for `changes`, milliseconds for `messaging`.
+    #[serde(
+        deserialize_with = "deserialize_timestamp",
+        serialize_with = "serialize_timestamp"
+    )]
     pub time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub changes: Vec<Instagram>,
+    /// [Instagram Messaging](https://developers.facebook.com/docs/messenger-platform/instagram/features/webhook) events.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messaging: Vec<InstagramMessaging>,
 }
 impl InstagramObjectEntry {
+    pub fn new(id: u64, time: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            time,
+            changes: vec![],
+            messaging: vec![],
+        }
+    }
+
+    pub fn with_change(mut self, change: Instagram) -> Self {
+        self.changes.push(change);
+        self
+    }
+
+    pub fn with_messaging(mut self, messaging: InstagramMessaging) -> Self {
+        self.messaging.push(messaging);
+        self
+    }
+
+    pub fn is_test(&self) -> bool {
+        self.id == 0
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PageObjectEntry {
+    /// id == [Page id](https://developers.facebook.com/docs/graph-api/reference/page)
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+    /// Seconds for `changes`, milliseconds for `messaging`.
+    #[serde(
+        deserialize_with = "deserialize_timestamp",
+        serialize_with = "serialize_timestamp"
+    )]
+    pub time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<Page>,
+    /// [Messenger Platform](https://developers.facebook.com/docs/messenger-platform/webhooks) events, always one item.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messaging: Vec<Messaging>,
+}
+impl PageObjectEntry {
+    pub fn new(id: u64, time: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            time,
+            changes: vec![],
+            messaging: vec![],
+        }
+    }
+
+    pub fn with_change(mut self, change: Page) -> Self {
+        self.changes.push(change);
+        self
+    }
+
+    pub fn with_messaging(mut self, messaging: Messaging) -> Self {
+        self.messaging.push(messaging);
+        self
+    }
+
     pub fn is_test(&self) -> bool {
         self.id == 0
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct PermissionsObjectEntry {
     /// id == uid == [FB Business Integration User ID](https://www.facebook.com/settings?tab=business_tools&ref=settings)
-    #[serde(deserialize_with = "deserialize_number_from_string")]
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
     pub id: u64,
-    #[serde(deserialize_with = "deserialize_number_from_string")]
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
     pub uid: u64,
     #[serde(with = "ts_seconds")]
     pub time: DateTime<Utc>,
     pub changes: Vec<Permissions>,
 }
 impl PermissionsObjectEntry {
+    pub fn new(uid: u64, time: DateTime<Utc>) -> Self {
+        Self {
+            id: uid,
+            uid,
+            time,
+            changes: vec![],
+        }
+    }
+
+    pub fn with_change(mut self, change: Permissions) -> Self {
+        self.changes.push(change);
+        self
+    }
+
+    pub fn is_test(&self) -> bool {
+        self.id == 0
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WhatsappBusinessAccountObjectEntry {
+    /// id == [WhatsApp Business Account id](https://developers.facebook.com/docs/graph-api/reference/whats-app-business-account)
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+    pub changes: Vec<Whatsapp>,
+}
+impl WhatsappBusinessAccountObjectEntry {
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            changes: vec![],
+        }
+    }
+
+    pub fn with_change(mut self, change: Whatsapp) -> Self {
+        self.changes.push(change);
+        self
+    }
+
     pub fn is_test(&self) -> bool {
         self.id == 0
     }
@@ -147,41 +493,191 @@ pub type PassBackCallbackFn<'a, C> = Box<
         + 'a,
 >;
 
-pub async fn pass_back<C>(
-    signature_header_value: &[u8],
+pub async fn pass_back<C, S: AsRef<str>>(
     request_body_bytes: &[u8],
-    app_secret: &str,
+    options: &VerifyOptions<'_, S>,
     ctx: C,
     callback: Arc<PassBackCallbackFn<'_, C>>,
 ) -> PassBackResponse {
-    match verify_payload(signature_header_value, request_body_bytes, app_secret) {
-        Ok(_) => match serde_json::from_slice::<Payload>(request_body_bytes) {
-            Ok(payload) => match callback(payload, ctx).await {
-                Ok(_) => PassBackResponse {
-                    status_code: StatusCode::OK,
-                    body: "".to_owned(),
-                },
-                Err(err) => PassBackResponse {
-                    status_code: StatusCode::INTERNAL_SERVER_ERROR,
-                    body: err.to_string(),
-                },
-            },
-            Err(err) => PassBackResponse {
-                status_code: StatusCode::INTERNAL_SERVER_ERROR,
-                body: err.to_string(),
-            },
+    let (payload, app_secret_index) = match verify_and_parse(request_body_bytes, options) {
+        Ok(x) => x,
+        Err(res) => return res,
+    };
+
+    match callback(payload, ctx).await {
+        Ok(_) => PassBackResponse {
+            status_code: StatusCode::OK,
+            body: "".to_owned(),
+            app_secret_index: Some(app_secret_index),
         },
-        Err(err) => match err {
-            VerifyPayloadError::SignatureHeaderValueInvalid(_) => PassBackResponse {
-                status_code: StatusCode::BAD_REQUEST,
-                body: err.to_string(),
+        Err(err) => PassBackResponse {
+            status_code: StatusCode::INTERNAL_SERVER_ERROR,
+            body: err.to_string(),
+            app_secret_index: Some(app_secret_index),
+        },
+    }
+}
+
+/// Same as [`pass_back`], but the duplicates are acked with `200` without calling `callback`.
+///
+/// `409` while the first copy is in flight, so Facebook retries the duplicate later.
+/// The fingerprint is marked done once `callback` succeeds, and forgotten when it fails, so the redelivery is processed.
+pub async fn pass_back_with_dedup<C, S: AsRef<str>>(
+    request_body_bytes: &[u8],
+    options: &VerifyOptions<'_, S>,
+    ctx: C,
+    callback: Arc<PassBackCallbackFn<'_, C>>,
+    dedup: &Dedup,
+) -> PassBackResponse {
+    let (payload, app_secret_index) = match verify_and_parse(request_body_bytes, options) {
+        Ok(x) => x,
+        Err(res) => return res,
+    };
+
+    let internal_server_error = |body: String| PassBackResponse {
+        status_code: StatusCode::INTERNAL_SERVER_ERROR,
+        body,
+        app_secret_index: Some(app_secret_index),
+    };
+
+    let fingerprint = match dedup::fingerprint(request_body_bytes, dedup.strategy) {
+        Ok(x) => x,
+        Err(err) => return internal_server_error(err.to_string()),
+    };
+    match dedup.store.insert(fingerprint).await {
+        Ok(DedupStatus::New) => {}
+        Ok(DedupStatus::InFlight) => {
+            return PassBackResponse {
+                status_code: StatusCode::CONFLICT,
+                body: "InFlight".to_owned(),
+                app_secret_index: Some(app_secret_index),
+            }
+        }
+        Ok(DedupStatus::Done) => {
+            return PassBackResponse {
+                status_code: StatusCode::OK,
+                body: "".to_owned(),
+                app_secret_index: Some(app_secret_index),
+            }
+        }
+        Err(err) => return internal_server_error(err.to_string()),
+    }
+
+    // The callback error isn't Send.
+    let ret = callback(payload, ctx).await.map_err(|err| err.to_string());
+    match ret {
+        Ok(_) => match dedup.store.mark_done(&fingerprint).await {
+            Ok(_) => PassBackResponse {
+                status_code: StatusCode::OK,
+                body: "".to_owned(),
+                app_secret_index: Some(app_secret_index),
             },
-            VerifyPayloadError::CalculateSignatureFailed
-            | VerifyPayloadError::SignatureMismatch => PassBackResponse {
+            Err(err) => internal_server_error(format!("MarkDoneFailed {}", err)),
+        },
+        Err(err) => match dedup.store.remove(&fingerprint).await {
+            Ok(_) => internal_server_error(err),
+            Err(remove_err) => {
+                internal_server_error(format!("{}, RemoveFailed {}", err, remove_err))
+            }
+        },
+    }
+}
+
+/// Same as [`pass_back`], but respond right after verifying and parsing, the payload is processed by the [`WorkQueue`].
+///
+/// `503` when the queue is full or closed, so Facebook retries later.
+#[cfg(feature = "with-queue")]
+pub async fn pass_back_with_queue<C, S: AsRef<str>>(
+    request_body_bytes: &[u8],
+    options: &VerifyOptions<'_, S>,
+    ctx: C,
+    queue: &WorkQueue<C>,
+) -> PassBackResponse
+where
+    C: Send + 'static,
+{
+    let (payload, app_secret_index) = match verify_and_parse(request_body_bytes, options) {
+        Ok(x) => x,
+        Err(res) => return res,
+    };
+
+    match queue.enqueue(payload, ctx).await {
+        Ok(_) => PassBackResponse {
+            status_code: StatusCode::OK,
+            body: "".to_owned(),
+            app_secret_index: Some(app_secret_index),
+        },
+        Err(err) => PassBackResponse {
+            status_code: StatusCode::SERVICE_UNAVAILABLE,
+            body: err.to_string(),
+            app_secret_index: Some(app_secret_index),
+        },
+    }
+}
+
+/// Same as [`pass_back`], but respond once the delivery is appended to the [`Spool`], process it by [`Spool::replay`].
+///
+/// `500` when the append fails, so Facebook retries later.
+#[cfg(feature = "with-spool")]
+pub async fn pass_back_with_spool<S: AsRef<str>>(
+    request_body_bytes: &[u8],
+    options: &VerifyOptions<'_, S>,
+    spool: &Spool,
+) -> PassBackResponse {
+    let app_secret_index = match verify_and_parse(request_body_bytes, options) {
+        Ok((_, app_secret_index)) => app_secret_index,
+        Err(res) => return res,
+    };
+
+    let mut headers = vec![];
+    if let Some(value) = options.signature_256_header_value {
+        headers.push((SIGNATURE_256_HEADER_NAME, value.to_vec()));
+    }
+    if let Some(value) = options.signature_header_value {
+        headers.push((SIGNATURE_HEADER_NAME, value.to_vec()));
+    }
+    let body = request_body_bytes.to_vec();
+
+    match spool::blocking(spool.clone(), move |spool| {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| (*name, &value[..]))
+            .collect::<Vec<_>>();
+        spool.append(&headers, &body)
+    })
+    .await
+    {
+        Ok(_) => PassBackResponse {
+            status_code: StatusCode::OK,
+            body: "".to_owned(),
+            app_secret_index: Some(app_secret_index),
+        },
+        Err(err) => PassBackResponse {
+            status_code: StatusCode::INTERNAL_SERVER_ERROR,
+            body: err.to_string(),
+            app_secret_index: Some(app_secret_index),
+        },
+    }
+}
+
+fn verify_and_parse<S: AsRef<str>>(
+    request_body_bytes: &[u8],
+    options: &VerifyOptions<'_, S>,
+) -> Result<(Payload, usize), PassBackResponse> {
+    match verify_payload_with_options(request_body_bytes, options) {
+        Ok(app_secret_index) => match serde_json::from_slice::<Payload>(request_body_bytes) {
+            Ok(payload) => Ok((payload, app_secret_index)),
+            Err(err) => Err(PassBackResponse {
                 status_code: StatusCode::INTERNAL_SERVER_ERROR,
                 body: err.to_string(),
-            },
+                app_secret_index: Some(app_secret_index),
+            }),
         },
+        Err(err) => Err(PassBackResponse {
+            status_code: err.status_code(),
+            body: err.to_string(),
+            app_secret_index: None,
+        }),
     }
 }
 
@@ -189,6 +685,8 @@ pub async fn pass_back<C>(
 pub struct PassBackResponse {
     pub status_code: StatusCode,
     pub body: String,
+    /// Index of the app secret the signature matched, `None` when it isn't verified.
+    pub app_secret_index: Option<usize>,
 }
 
 #[cfg(test)]
@@ -203,16 +701,401 @@ mod tests {
                 .unwrap(),
             Signature::Sha1("57443a4c052350a44638835d64fd66822f813319".to_owned())
         );
+        assert_eq!(
+            "sha256=90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481"
+                .parse::<Signature>()
+                .unwrap(),
+            Signature::Sha256(
+                "90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481".to_owned()
+            )
+        );
+        assert_eq!(
+            "sha256=57443a4c052350a44638835d64fd66822f813319".parse::<Signature>(),
+            Err("value length invalid")
+        );
     }
 
     #[test]
     fn test_hmac_sha1_payload() {
         assert_eq!(
-            hmac_sha1_payload(b"value", "key").unwrap(),
+            hex::encode(
+                hmac_sha1_payload(b"value", "key")
+                    .unwrap()
+                    .finalize()
+                    .into_bytes()
+            ),
             "57443a4c052350a44638835d64fd66822f813319"
         );
     }
 
+    #[test]
+    fn test_hmac_sha256_payload() {
+        assert_eq!(
+            hex::encode(
+                hmac_sha256_payload(b"value", "key")
+                    .unwrap()
+                    .finalize()
+                    .into_bytes()
+            ),
+            "90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481"
+        );
+    }
+
+    #[test]
+    fn test_verify_payload() {
+        verify_payload(
+            b"sha1=57443a4c052350a44638835d64fd66822f813319",
+            b"value",
+            "key",
+        )
+        .unwrap();
+
+        // mixed-case hex
+        verify_payload(
+            b"sha1=57443A4C052350a44638835D64FD66822f813319",
+            b"value",
+            "key",
+        )
+        .unwrap();
+        verify_payload(
+            b"sha256=90FBFCF15E74A36B89DBDB2A721D9AECffdfdddc5c83e27f7592594f71932481",
+            b"value",
+            "key",
+        )
+        .unwrap();
+
+        // bad hex
+        match verify_payload(
+            b"sha1=zz443a4c052350a44638835d64fd66822f813319",
+            b"value",
+            "key",
+        ) {
+            Err(VerifyPayloadError::SignatureHeaderValueInvalid(_)) => {}
+            x => panic!("{:?}", x),
+        }
+
+        // wrong-length digests
+        match verify_payload(
+            b"sha1=57443a4c052350a44638835d64fd66822f8133",
+            b"value",
+            "key",
+        ) {
+            Err(VerifyPayloadError::SignatureHeaderValueInvalid(_)) => {}
+            x => panic!("{:?}", x),
+        }
+        match verify_payload_with_signature(
+            Signature::Sha1("57443a4c052350a44638835d64fd66822f8133".to_owned()),
+            b"value",
+            &VerifyOptions::new(&["key"]),
+        ) {
+            Err(VerifyPayloadError::SignatureMismatch) => {}
+            x => panic!("{:?}", x),
+        }
+        match verify_payload_with_signature(
+            Signature::Sha256("57443a4c052350a44638835d64fd66822f813319".to_owned()),
+            b"value",
+            &VerifyOptions::new(&["key"]),
+        ) {
+            Err(VerifyPayloadError::SignatureMismatch) => {}
+            x => panic!("{:?}", x),
+        }
+
+        match verify_payload(
+            b"sha1=57443a4c052350a44638835d64fd66822f813319",
+            b"value",
+            "other",
+        ) {
+            Err(VerifyPayloadError::SignatureMismatch) => {}
+            x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_verify_payload_with_options() {
+        let sha1 = b"sha1=57443a4c052350a44638835d64fd66822f813319";
+        let sha256 = b"sha256=90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481";
+        let sha256_wrong =
+            b"sha256=0000000000000000000000000000000000000000000000000000000000000000";
+
+        verify_payload_with_options(
+            b"value",
+            &VerifyOptions::new(&["key"])
+                .with_signature_headers(Some(sha256), Some(sha1))
+                .with_policy(SignaturePolicy::AllowSha1),
+        )
+        .unwrap();
+        verify_payload_with_options(
+            b"value",
+            &VerifyOptions::new(&["key"])
+                .with_signature_headers(None, Some(sha1))
+                .with_policy(SignaturePolicy::AllowSha1),
+        )
+        .unwrap();
+        verify_payload_with_options(
+            b"value",
+            &VerifyOptions::new(&["key"])
+                .with_signature_headers(Some(sha256), None)
+                .with_policy(SignaturePolicy::RequireSha256),
+        )
+        .unwrap();
+
+        match verify_payload_with_options(
+            b"value",
+            &VerifyOptions::new(&["key"]).with_signature_headers(Some(sha256_wrong), Some(sha1)),
+        ) {
+            Err(VerifyPayloadError::SignatureMismatch) => {}
+            x => panic!("{:?}", x),
+        }
+
+        match verify_payload_with_options(
+            b"value",
+            &VerifyOptions::new(&["key"])
+                .with_signature_headers(None, Some(sha1))
+                .with_policy(SignaturePolicy::RequireSha256),
+        ) {
+            Err(VerifyPayloadError::SignatureAlgorithmNotAllowed) => {}
+            x => panic!("{:?}", x),
+        }
+
+        match verify_payload_with_options(
+            b"value",
+            &VerifyOptions::new(&["key"])
+                .with_signature_headers(None, None)
+                .with_policy(SignaturePolicy::AllowSha1),
+        ) {
+            Err(VerifyPayloadError::SignatureHeaderMissing) => {}
+            x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_verify_payload_with_secrets() {
+        let sha1 = b"sha1=57443a4c052350a44638835d64fd66822f813319";
+        let sha256 = b"sha256=90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481";
+
+        let verify = |app_secrets: &[&str]| {
+            verify_payload_with_options(
+                b"value",
+                &VerifyOptions::new(app_secrets).with_signature_headers(None, Some(sha1)),
+            )
+        };
+        assert_eq!(verify(&["key", "other"]).unwrap(), 0);
+        assert_eq!(verify(&["other", "key"]).unwrap(), 1);
+        assert_eq!(
+            verify_payload_with_options(
+                b"value",
+                &VerifyOptions::new(&["other".to_owned(), "key".to_owned()])
+                    .with_signature_headers(Some(sha256), None)
+                    .with_policy(SignaturePolicy::RequireSha256),
+            )
+            .unwrap(),
+            1
+        );
+
+        match verify(&["other"]) {
+            Err(VerifyPayloadError::SignatureMismatch) => {}
+            x => panic!("{:?}", x),
+        }
+        match verify(&[]) {
+            Err(VerifyPayloadError::SignatureMismatch) => {}
+            x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_sign_payload() {
+        assert_eq!(
+            sign_payload(b"value", "key", SignatureAlgorithm::Sha1).unwrap(),
+            "sha1=57443a4c052350a44638835d64fd66822f813319"
+        );
+        assert_eq!(
+            sign_payload(b"value", "key", SignatureAlgorithm::Sha256).unwrap(),
+            "sha256=90fbfcf15e74a36b89dbdb2a721d9aecffdfdddc5c83e27f7592594f71932481"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pass_back_with_built_payload() {
+        use chrono::TimeZone as _;
+
+        use crate::topics::page::{Author, FeedStatusValue, FeedValue, NameValue, Verb};
+
+        let time = Utc.timestamp_opt(1624005617, 0).unwrap();
+        let payload = Payload::Page(vec![PageObjectEntry::new(1, time)
+            .with_change(Page::Name(NameValue {
+                verb: Some(Verb::Edited),
+                value: Some("foo".to_owned()),
+            }))
+            .with_change(Page::Feed(FeedValue::Status(FeedStatusValue {
+                post_id: "1_2".to_owned(),
+                verb: Verb::Add,
+                from: Some(Author {
+                    id: 1,
+                    name: Some("foo".to_owned()),
+                }),
+                message: None,
+                published: Some(1),
+                created_time: Some(time),
+            })))]);
+
+        let (body, signature) = payload
+            .to_signed("key", SignatureAlgorithm::Sha256)
+            .unwrap();
+
+        let callback: Arc<PassBackCallbackFn<'_, ()>> = Arc::new(Box::new(move |payload, _ctx| {
+            Box::pin(async move {
+                match payload {
+                    Payload::Page(entries) => {
+                        assert_eq!(entries[0].id, 1);
+                        assert_eq!(entries[0].time, time);
+                        match &entries[0].changes[..] {
+                            [Page::Name(v), Page::Feed(FeedValue::Status(_))] => {
+                                assert_eq!(v.value.as_deref(), Some("foo"));
+                            }
+                            x => panic!("{:?}", x),
+                        }
+                        Ok(())
+                    }
+                    x => Err(format!("{:?}", x).into()),
+                }
+            })
+        }));
+
+        let res = pass_back(
+            &body,
+            &VerifyOptions::new(&["key"])
+                .with_signature_headers(Some(signature.as_bytes()), None)
+                .with_policy(SignaturePolicy::RequireSha256),
+            (),
+            callback.clone(),
+        )
+        .await;
+        assert_eq!(res.status_code, StatusCode::OK, "{}", res.body);
+
+        let res = pass_back(
+            &body,
+            &VerifyOptions::new(&["other"])
+                .with_signature_headers(Some(signature.as_bytes()), None)
+                .with_policy(SignaturePolicy::RequireSha256),
+            (),
+            callback,
+        )
+        .await;
+        assert_eq!(res.status_code, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_pass_back_with_dedup() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::{
+            context::ContextError,
+            dedup::{DedupStore, Fingerprint, InMemoryDedupStore},
+        };
+
+        let body = br#"{"object":"user","entry":[]}"#;
+        let signature = format!(
+            "sha256={}",
+            hex::encode(
+                hmac_sha256_payload(body, "key")
+                    .unwrap()
+                    .finalize()
+                    .into_bytes()
+            )
+        );
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let callback: Arc<PassBackCallbackFn<'_, ()>> = {
+            let calls = calls.clone();
+            Arc::new(Box::new(move |_payload, _ctx| {
+                let n = calls.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    if n == 0 {
+                        Err("failed once".into())
+                    } else {
+                        Ok(())
+                    }
+                })
+            }))
+        };
+        let dedup = Dedup::new(InMemoryDedupStore::default());
+
+        let mut status_codes = vec![];
+        for _ in 0..3 {
+            let res = pass_back_with_dedup(
+                body,
+                &VerifyOptions::new(&["key"])
+                    .with_signature_headers(Some(signature.as_bytes()), None)
+                    .with_policy(SignaturePolicy::RequireSha256),
+                (),
+                callback.clone(),
+                &dedup,
+            )
+            .await;
+            status_codes.push(res.status_code);
+        }
+        assert_eq!(
+            status_codes,
+            vec![
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::OK,
+                StatusCode::OK
+            ]
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // In flight.
+        let body = br#"{"object":"user","entry":[{"uid":"1","time":1,"changes":[]}]}"#;
+        let signature = sign_payload(body, "key", SignatureAlgorithm::Sha256).unwrap();
+        let options =
+            VerifyOptions::new(&["key"]).with_signature_headers(Some(signature.as_bytes()), None);
+        let fingerprint = dedup::fingerprint(body, dedup.strategy).unwrap();
+        assert_eq!(
+            dedup.store.insert(fingerprint).await.unwrap(),
+            DedupStatus::New
+        );
+        let res = pass_back_with_dedup(body, &options, (), callback.clone(), &dedup).await;
+        assert_eq!(res.status_code, StatusCode::CONFLICT);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        dedup.store.mark_done(&fingerprint).await.unwrap();
+        let res = pass_back_with_dedup(body, &options, (), callback.clone(), &dedup).await;
+        assert_eq!(res.status_code, StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // The remove error is returned.
+        struct RemoveFailedStore;
+        #[async_trait::async_trait]
+        impl DedupStore for RemoveFailedStore {
+            async fn insert(&self, _: Fingerprint) -> Result<DedupStatus, ContextError> {
+                Ok(DedupStatus::New)
+            }
+            async fn mark_done(&self, _: &Fingerprint) -> Result<(), ContextError> {
+                Ok(())
+            }
+            async fn remove(&self, _: &Fingerprint) -> Result<(), ContextError> {
+                Err("store down".into())
+            }
+        }
+        let callback: Arc<PassBackCallbackFn<'_, ()>> = Arc::new(Box::new(|_payload, _ctx| {
+            Box::pin(async { Err("foo".into()) })
+        }));
+        let res =
+            pass_back_with_dedup(body, &options, (), callback, &Dedup::new(RemoveFailedStore))
+                .await;
+        assert_eq!(res.status_code, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(res.body, "foo, RemoveFailed store down");
+    }
+
+    /// Parse, then check that the serialized payload parses to the same.
+    fn from_str_round_trip(json: &str) -> Result<Payload, serde_json::Error> {
+        let payload = serde_json::from_str::<Payload>(json)?;
+        let value = serde_json::to_value(&payload)?;
+        let payload = serde_json::from_value::<Payload>(value.clone())?;
+        assert_eq!(serde_json::to_value(&payload)?, value);
+        Ok(payload)
+    }
+
     #[test]
     fn test_payload() {
         let json = r#"
@@ -241,7 +1124,7 @@ mod tests {
         }
         "#;
 
-        match serde_json::from_str::<Payload>(json) {
+        match from_str_round_trip(json) {
             Ok(Payload::Instagram(entry_vec)) => {
                 println!("{:?}", entry_vec);
 
@@ -254,6 +1137,90 @@ mod tests {
             Err(err) => panic!("{}", err),
         }
 
+        let json = r#"
+        {
+            "object": "page",
+            "entry": [
+                {
+                    "id": "682498171943165",
+                    "time": 1458692752478,
+                    "messaging": [
+                        {
+                            "sender": {
+                                "id": "1254459154682919"
+                            },
+                            "recipient": {
+                                "id": "682498171943165"
+                            },
+                            "timestamp": 1458692752478,
+                            "message": {
+                                "mid": "mid.1457764197618:41d102a3e1ae206a38",
+                                "text": "hello, world!"
+                            }
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+        match from_str_round_trip(json) {
+            Ok(Payload::Page(entry_vec)) => {
+                println!("{:?}", entry_vec);
+
+                assert_eq!(entry_vec.len(), 1);
+                let entry = entry_vec.first().unwrap();
+                assert_eq!(entry.id, 682498171943165);
+                assert_eq!(entry.time.timestamp_millis(), 1458692752478);
+                assert_eq!(entry.messaging.len(), 1);
+                assert!(entry.changes.is_empty());
+            }
+            Ok(payload) => panic!("{:?}", payload),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "object": "page",
+            "entry": [
+                {
+                    "id": "0",
+                    "time": 1520544816,
+                    "changes": [
+                        {
+                            "field": "feed",
+                            "value": {
+                                "item": "post",
+                                "post_id": "44444444_444444444",
+                                "verb": "add",
+                                "published": 1,
+                                "created_time": 1520544814,
+                                "message": "Example post content.",
+                                "from": {
+                                    "name": "Test Page",
+                                    "id": "1067280970047460"
+                                }
+                            }
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+        match from_str_round_trip(json) {
+            Ok(Payload::Page(entry_vec)) => {
+                println!("{:?}", entry_vec);
+
+                assert_eq!(entry_vec.len(), 1);
+                let entry = entry_vec.first().unwrap();
+                assert!(entry.is_test());
+                assert_eq!(entry.time.timestamp(), 1520544816);
+                assert_eq!(entry.changes.len(), 1);
+                assert!(entry.messaging.is_empty());
+            }
+            Ok(payload) => panic!("{:?}", payload),
+            Err(err) => panic!("{}", err),
+        }
+
         let json = r#"
         {
             "object": "permissions",
@@ -278,7 +1245,7 @@ mod tests {
             ]
         }
         "#;
-        match serde_json::from_str::<Payload>(json) {
+        match from_str_round_trip(json) {
             Ok(Payload::Permissions(entry_vec)) => {
                 println!("{:?}", entry_vec);
 
@@ -290,5 +1257,157 @@ mod tests {
             Ok(payload) => panic!("{:?}", payload),
             Err(err) => panic!("{}", err),
         }
+
+        let json = r#"
+        {
+            "object": "instagram",
+            "entry": [
+                {
+                    "id": "0",
+                    "time": 1624005617,
+                    "changes": [
+                        {
+                            "field": "foo_bar",
+                            "value": {}
+                        },
+                        {
+                            "field": "mentions",
+                            "value": {
+                                "media_id": "17887498072083520",
+                                "comment_id": "17894227972186120"
+                            }
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+        match from_str_round_trip(json) {
+            Ok(Payload::Instagram(entry_vec)) => {
+                println!("{:?}", entry_vec);
+
+                let entry = entry_vec.first().unwrap();
+                assert_eq!(entry.changes.len(), 2);
+                assert!(matches!(entry.changes[0], Instagram::Unknown { .. }));
+                assert!(matches!(entry.changes[1], Instagram::Mentions(_)));
+            }
+            Ok(payload) => panic!("{:?}", payload),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "object": "instagram",
+            "entry": [
+                {
+                    "id": "17841405309211844",
+                    "time": 1603059201000,
+                    "messaging": [
+                        {
+                            "sender": {
+                                "id": "1254459154682919"
+                            },
+                            "recipient": {
+                                "id": "17841405309211844"
+                            },
+                            "timestamp": 1603059201000,
+                            "message": {
+                                "mid": "aWdfZAG1faXRlbToxOklHTWVzc2FnZAUlEOjE3ODQxNDA1MzA5MjExODQ0",
+                                "text": "hello"
+                            }
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+        match from_str_round_trip(json) {
+            Ok(Payload::Instagram(entry_vec)) => {
+                println!("{:?}", entry_vec);
+
+                let entry = entry_vec.first().unwrap();
+                assert_eq!(entry.time.timestamp(), 1603059201);
+                assert!(entry.changes.is_empty());
+                assert_eq!(entry.messaging.len(), 1);
+            }
+            Ok(payload) => panic!("{:?}", payload),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "object": "user",
+            "entry": [
+                {
+                    "id": "0",
+                    "uid": "0",
+                    "time": 1624610156,
+                    "changed_fields": ["email"]
+                }
+            ]
+        }
+        "#;
+        match from_str_round_trip(json) {
+            Ok(Payload::Other { object, entry }) => {
+                assert_eq!(object, "user");
+                assert_eq!(entry.len(), 1);
+            }
+            Ok(payload) => panic!("{:?}", payload),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "object": "whatsapp_business_account",
+            "entry": [
+                {
+                    "id": "0",
+                    "changes": [
+                        {
+                            "field": "messages",
+                            "value": {
+                                "messaging_product": "whatsapp",
+                                "metadata": {
+                                    "display_phone_number": "16505551111",
+                                    "phone_number_id": "123456123"
+                                },
+                                "contacts": [
+                                    {
+                                        "profile": {
+                                            "name": "test user name"
+                                        },
+                                        "wa_id": "16315551181"
+                                    }
+                                ],
+                                "messages": [
+                                    {
+                                        "from": "16315551181",
+                                        "id": "ABGGFlA5Fpa",
+                                        "timestamp": "1504902988",
+                                        "type": "text",
+                                        "text": {
+                                            "body": "this is a text message"
+                                        }
+                                    }
+                                ]
+                            }
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+        match from_str_round_trip(json) {
+            Ok(Payload::WhatsappBusinessAccount(entry_vec)) => {
+                println!("{:?}", entry_vec);
+
+                assert_eq!(entry_vec.len(), 1);
+                let entry = entry_vec.first().unwrap();
+                assert!(entry.is_test());
+                assert_eq!(entry.changes.len(), 1);
+            }
+            Ok(payload) => panic!("{:?}", payload),
+            Err(err) => panic!("{}", err),
+        }
     }
 }
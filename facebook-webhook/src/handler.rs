@@ -0,0 +1,1195 @@
+//! Per-topic, per-field dispatch of [`Payload`].
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use facebook_permission::FacebookPermission;
+use serde_json::Value;
+
+use crate::{
+    event_notifications::{
+        InstagramObjectEntry, PageObjectEntry, PassBackCallbackFn, Payload, PermissionsObjectEntry,
+        WhatsappBusinessAccountObjectEntry,
+    },
+    topics::{
+        instagram::{CommentsValue, Instagram, MentionsValue, StoryInsightsValue},
+        instagram_messaging::{
+            Message as InstagramMessage, Messaging as InstagramMessaging,
+            MessagingEvent as InstagramMessagingEvent, Postback as InstagramPostback,
+            Reaction as InstagramReaction, Read as InstagramRead, Referral as InstagramReferral,
+            ThreadControl as InstagramThreadControl,
+        },
+        messaging::Participant,
+        messenger::{
+            Delivery as MessengerDelivery, Message as MessengerMessage,
+            Messaging as MessengerMessaging, MessagingEvent as MessengerMessagingEvent,
+            Optin as MessengerOptin, Postback as MessengerPostback, Reaction as MessengerReaction,
+            Read as MessengerRead, Referral as MessengerReferral,
+        },
+        page::{
+            FeedValue, LeadgenValue, LiveVideosValue, MentionValue, NameValue, Page, PictureValue,
+            RatingsValue, VideosValue,
+        },
+        permissions::{FieldFlattenValue, Permissions, Value as PermissionsValue},
+        whatsapp::{
+            MessageTemplateStatusUpdateValue, MessagesValue, PhoneNumberQualityUpdateValue,
+            Whatsapp,
+        },
+    },
+};
+
+pub type HandlerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// `entry[]` fields shared by every change of the entry.
+#[derive(Debug, Clone)]
+pub struct EntryMeta {
+    pub object: String,
+    pub id: u64,
+    /// Only for `permissions`.
+    pub uid: Option<u64>,
+    /// Missing for `whatsapp_business_account`.
+    pub time: Option<DateTime<Utc>>,
+}
+impl EntryMeta {
+    pub fn is_test(&self) -> bool {
+        self.id == 0
+    }
+}
+
+/// `entry[].messaging[]` fields shared by every event.
+#[derive(Debug, Clone)]
+pub struct MessagingMeta {
+    pub sender: Participant,
+    pub recipient: Participant,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+pub type MessengerMessagingMeta = MessagingMeta;
+pub type InstagramMessagingMeta = MessagingMeta;
+
+/// Every method is a no-op by default, implement the ones subscribed to.
+#[async_trait]
+pub trait WebhookHandler<C>: Send + Sync
+where
+    C: Send + Sync,
+{
+    //
+    async fn on_instagram_comment(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: CommentsValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_live_comment(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: CommentsValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_mention(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: MentionsValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_story_insights(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: StoryInsightsValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    /// Both `entry[].messaging[]` and the messaging fields of `entry[].changes[]`, so are the others.
+    async fn on_instagram_message(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &InstagramMessagingMeta,
+        _value: InstagramMessage,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_reaction(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &InstagramMessagingMeta,
+        _value: InstagramReaction,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_postback(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &InstagramMessagingMeta,
+        _value: InstagramPostback,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_read(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &InstagramMessagingMeta,
+        _value: InstagramRead,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_referral(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &InstagramMessagingMeta,
+        _value: InstagramReferral,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_pass_thread_control(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &InstagramMessagingMeta,
+        _value: InstagramThreadControl,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_take_thread_control(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &InstagramMessagingMeta,
+        _value: InstagramThreadControl,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_request_thread_control(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &InstagramMessagingMeta,
+        _value: InstagramThreadControl,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_instagram_standby(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: InstagramMessaging,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+
+    //
+    async fn on_page_feed(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: FeedValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_page_mention(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: MentionValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_page_ratings(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: RatingsValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_page_leadgen(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: LeadgenValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_page_live_videos(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: LiveVideosValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_page_videos(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: VideosValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_page_name(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: NameValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_page_picture(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: PictureValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    /// `entry[].messaging[]` of the `page` object, so are the others. Including the echoes, see `is_echo`.
+    async fn on_messenger_message(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &MessengerMessagingMeta,
+        _value: MessengerMessage,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_messenger_postback(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &MessengerMessagingMeta,
+        _value: MessengerPostback,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_messenger_delivery(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &MessengerMessagingMeta,
+        _value: MessengerDelivery,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_messenger_read(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &MessengerMessagingMeta,
+        _value: MessengerRead,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_messenger_reaction(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &MessengerMessagingMeta,
+        _value: MessengerReaction,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_messenger_referral(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &MessengerMessagingMeta,
+        _value: MessengerReferral,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_messenger_optin(
+        &self,
+        _entry_meta: &EntryMeta,
+        _messaging_meta: &MessengerMessagingMeta,
+        _value: MessengerOptin,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+
+    //
+    async fn on_permissions_connected(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: FieldFlattenValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_permission_changed(
+        &self,
+        _entry_meta: &EntryMeta,
+        _permission: FacebookPermission,
+        _value: PermissionsValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+
+    //
+    async fn on_whatsapp_messages(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: MessagesValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_whatsapp_message_template_status_update(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: MessageTemplateStatusUpdateValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    async fn on_whatsapp_phone_number_quality_update(
+        &self,
+        _entry_meta: &EntryMeta,
+        _value: PhoneNumberQualityUpdateValue,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+
+    //
+    /// Change of a known object, but its field is unknown or its value can't be parsed yet.
+    ///
+    /// So is the unknown `entry[].messaging[]` event, `field` is its key.
+    async fn on_unknown_change(
+        &self,
+        _entry_meta: &EntryMeta,
+        _field: String,
+        _value: Value,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+    /// Whole entry of an unknown object.
+    async fn on_other_object_entry(
+        &self,
+        _object: &str,
+        _entry: Value,
+        _ctx: &C,
+    ) -> Result<(), HandlerError> {
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("{} of the changes failed", .0.len())]
+pub struct DispatchError(pub Vec<ChangeError>);
+
+#[derive(thiserror::Error, Debug)]
+#[error("object {object} entry {entry_id:?} field {field} failed: {error}")]
+pub struct ChangeError {
+    pub object: String,
+    /// Missing for entries of an unknown object.
+    pub entry_id: Option<u64>,
+    pub field: String,
+    #[source]
+    pub error: HandlerError,
+}
+
+/// Call the [`WebhookHandler`] method of every change, a failed change doesn't stop the others.
+pub async fn dispatch<C, H>(payload: Payload, handler: &H, ctx: &C) -> Result<(), DispatchError>
+where
+    C: Send + Sync,
+    H: WebhookHandler<C> + ?Sized,
+{
+    let mut errors = vec![];
+
+    match payload {
+        Payload::Instagram(entry_vec) => {
+            for entry in entry_vec {
+                dispatch_instagram_entry(entry, handler, ctx, &mut errors).await;
+            }
+        }
+        Payload::Page(entry_vec) => {
+            for entry in entry_vec {
+                dispatch_page_entry(entry, handler, ctx, &mut errors).await;
+            }
+        }
+        Payload::Permissions(entry_vec) => {
+            for entry in entry_vec {
+                dispatch_permissions_entry(entry, handler, ctx, &mut errors).await;
+            }
+        }
+        Payload::WhatsappBusinessAccount(entry_vec) => {
+            for entry in entry_vec {
+                dispatch_whatsapp_business_account_entry(entry, handler, ctx, &mut errors).await;
+            }
+        }
+        Payload::Other { object, entry } => {
+            for entry in entry {
+                if let Err(error) = handler.on_other_object_entry(&object, entry, ctx).await {
+                    errors.push(ChangeError {
+                        object: object.to_owned(),
+                        entry_id: None,
+                        field: "".to_owned(),
+                        error,
+                    });
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(DispatchError(errors))
+    }
+}
+
+/// Wrap a [`WebhookHandler`] as the callback of [`crate::event_notifications::pass_back`].
+pub fn pass_back_callback<C, H>(handler: Arc<H>) -> PassBackCallbackFn<'static, C>
+where
+    C: Send + Sync + 'static,
+    H: WebhookHandler<C> + ?Sized + 'static,
+{
+    Box::new(move |payload, ctx| {
+        let handler = handler.clone();
+        Box::pin(async move {
+            dispatch(payload, handler.as_ref(), &ctx)
+                .await
+                .map_err(|err| err.into())
+        })
+    })
+}
+
+async fn dispatch_instagram_entry<C, H>(
+    entry: InstagramObjectEntry,
+    handler: &H,
+    ctx: &C,
+    errors: &mut Vec<ChangeError>,
+) where
+    C: Send + Sync,
+    H: WebhookHandler<C> + ?Sized,
+{
+    let entry_meta = EntryMeta {
+        object: "instagram".to_owned(),
+        id: entry.id,
+        uid: None,
+        time: Some(entry.time),
+    };
+
+    for change in entry.changes {
+        let (field, ret) = match change {
+            Instagram::Comments(v) => (
+                "comments".to_owned(),
+                handler.on_instagram_comment(&entry_meta, v, ctx).await,
+            ),
+            Instagram::LiveComments(v) => (
+                "live_comments".to_owned(),
+                handler.on_instagram_live_comment(&entry_meta, v, ctx).await,
+            ),
+            Instagram::Mentions(v) => (
+                "mentions".to_owned(),
+                handler.on_instagram_mention(&entry_meta, v, ctx).await,
+            ),
+            Instagram::StoryInsights(v) => (
+                "story_insights".to_owned(),
+                handler
+                    .on_instagram_story_insights(&entry_meta, v, ctx)
+                    .await,
+            ),
+            Instagram::Standby(v) => (
+                "standby".to_owned(),
+                handler.on_instagram_standby(&entry_meta, v, ctx).await,
+            ),
+            Instagram::MessageReactions(v)
+            | Instagram::Messages(v)
+            | Instagram::MessagingHandover(v)
+            | Instagram::MessagingPostbacks(v)
+            | Instagram::MessagingReferral(v)
+            | Instagram::MessagingSeen(v) => {
+                dispatch_instagram_messaging(&entry_meta, v, handler, ctx).await
+            }
+            Instagram::Unknown { field, value } => (
+                field.to_owned(),
+                handler
+                    .on_unknown_change(&entry_meta, field, value, ctx)
+                    .await,
+            ),
+        };
+        push_error(&entry_meta, field, ret, errors);
+    }
+
+    for messaging in entry.messaging {
+        let (field, ret) = dispatch_instagram_messaging(&entry_meta, messaging, handler, ctx).await;
+        push_error(&entry_meta, field, ret, errors);
+    }
+}
+
+async fn dispatch_instagram_messaging<C, H>(
+    entry_meta: &EntryMeta,
+    messaging: InstagramMessaging,
+    handler: &H,
+    ctx: &C,
+) -> (String, Result<(), HandlerError>)
+where
+    C: Send + Sync,
+    H: WebhookHandler<C> + ?Sized,
+{
+    let field = messaging.event.field().to_owned();
+    let messaging_meta = InstagramMessagingMeta {
+        sender: messaging.sender,
+        recipient: messaging.recipient,
+        timestamp: messaging.timestamp,
+    };
+
+    let ret = match messaging.event {
+        InstagramMessagingEvent::Message(v) => {
+            handler
+                .on_instagram_message(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        InstagramMessagingEvent::Reaction(v) => {
+            handler
+                .on_instagram_reaction(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        InstagramMessagingEvent::Postback(v) => {
+            handler
+                .on_instagram_postback(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        InstagramMessagingEvent::Read(v) => {
+            handler
+                .on_instagram_read(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        InstagramMessagingEvent::Referral(v) => {
+            handler
+                .on_instagram_referral(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        InstagramMessagingEvent::PassThreadControl(v) => {
+            handler
+                .on_instagram_pass_thread_control(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        InstagramMessagingEvent::TakeThreadControl(v) => {
+            handler
+                .on_instagram_take_thread_control(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        InstagramMessagingEvent::RequestThreadControl(v) => {
+            handler
+                .on_instagram_request_thread_control(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        InstagramMessagingEvent::Unknown(v) => {
+            handler
+                .on_unknown_change(entry_meta, field.to_owned(), v, ctx)
+                .await
+        }
+    };
+
+    (field, ret)
+}
+
+async fn dispatch_page_entry<C, H>(
+    entry: PageObjectEntry,
+    handler: &H,
+    ctx: &C,
+    errors: &mut Vec<ChangeError>,
+) where
+    C: Send + Sync,
+    H: WebhookHandler<C> + ?Sized,
+{
+    let entry_meta = EntryMeta {
+        object: "page".to_owned(),
+        id: entry.id,
+        uid: None,
+        time: Some(entry.time),
+    };
+
+    for change in entry.changes {
+        let (field, ret) = match change {
+            Page::Feed(v) => (
+                "feed".to_owned(),
+                handler.on_page_feed(&entry_meta, v, ctx).await,
+            ),
+            Page::Mention(v) => (
+                "mention".to_owned(),
+                handler.on_page_mention(&entry_meta, v, ctx).await,
+            ),
+            Page::Ratings(v) => (
+                "ratings".to_owned(),
+                handler.on_page_ratings(&entry_meta, v, ctx).await,
+            ),
+            Page::Leadgen(v) => (
+                "leadgen".to_owned(),
+                handler.on_page_leadgen(&entry_meta, v, ctx).await,
+            ),
+            Page::LiveVideos(v) => (
+                "live_videos".to_owned(),
+                handler.on_page_live_videos(&entry_meta, v, ctx).await,
+            ),
+            Page::Videos(v) => (
+                "videos".to_owned(),
+                handler.on_page_videos(&entry_meta, v, ctx).await,
+            ),
+            Page::Name(v) => (
+                "name".to_owned(),
+                handler.on_page_name(&entry_meta, v, ctx).await,
+            ),
+            Page::Picture(v) => (
+                "picture".to_owned(),
+                handler.on_page_picture(&entry_meta, v, ctx).await,
+            ),
+            Page::Unknown { field, value } => (
+                field.to_owned(),
+                handler
+                    .on_unknown_change(&entry_meta, field, value, ctx)
+                    .await,
+            ),
+        };
+        push_error(&entry_meta, field, ret, errors);
+    }
+
+    for messaging in entry.messaging {
+        let (field, ret) = dispatch_messenger_messaging(&entry_meta, messaging, handler, ctx).await;
+        push_error(&entry_meta, field, ret, errors);
+    }
+}
+
+async fn dispatch_messenger_messaging<C, H>(
+    entry_meta: &EntryMeta,
+    messaging: MessengerMessaging,
+    handler: &H,
+    ctx: &C,
+) -> (String, Result<(), HandlerError>)
+where
+    C: Send + Sync,
+    H: WebhookHandler<C> + ?Sized,
+{
+    let field = messaging.event.field().to_owned();
+    let messaging_meta = MessengerMessagingMeta {
+        sender: messaging.sender,
+        recipient: messaging.recipient,
+        timestamp: messaging.timestamp,
+    };
+
+    let ret = match messaging.event {
+        MessengerMessagingEvent::Message(v) => {
+            handler
+                .on_messenger_message(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        MessengerMessagingEvent::Postback(v) => {
+            handler
+                .on_messenger_postback(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        MessengerMessagingEvent::Delivery(v) => {
+            handler
+                .on_messenger_delivery(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        MessengerMessagingEvent::Read(v) => {
+            handler
+                .on_messenger_read(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        MessengerMessagingEvent::Reaction(v) => {
+            handler
+                .on_messenger_reaction(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        MessengerMessagingEvent::Referral(v) => {
+            handler
+                .on_messenger_referral(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        MessengerMessagingEvent::Optin(v) => {
+            handler
+                .on_messenger_optin(entry_meta, &messaging_meta, v, ctx)
+                .await
+        }
+        MessengerMessagingEvent::Unknown(v) => {
+            handler
+                .on_unknown_change(entry_meta, field.to_owned(), v, ctx)
+                .await
+        }
+    };
+
+    (field, ret)
+}
+
+async fn dispatch_permissions_entry<C, H>(
+    entry: PermissionsObjectEntry,
+    handler: &H,
+    ctx: &C,
+    errors: &mut Vec<ChangeError>,
+) where
+    C: Send + Sync,
+    H: WebhookHandler<C> + ?Sized,
+{
+    let entry_meta = EntryMeta {
+        object: "permissions".to_owned(),
+        id: entry.id,
+        uid: Some(entry.uid),
+        time: Some(entry.time),
+    };
+
+    for change in entry.changes {
+        let (field, ret) = match change {
+            Permissions::Connected(v) => (
+                "connected".to_owned(),
+                handler.on_permissions_connected(&entry_meta, v, ctx).await,
+            ),
+            Permissions::Permission(v) => (
+                v.field.to_string(),
+                handler
+                    .on_permission_changed(&entry_meta, v.field, v.value, ctx)
+                    .await,
+            ),
+            Permissions::Unknown { field, value } => (
+                field.to_owned(),
+                handler
+                    .on_unknown_change(&entry_meta, field, value, ctx)
+                    .await,
+            ),
+        };
+        push_error(&entry_meta, field, ret, errors);
+    }
+}
+
+async fn dispatch_whatsapp_business_account_entry<C, H>(
+    entry: WhatsappBusinessAccountObjectEntry,
+    handler: &H,
+    ctx: &C,
+    errors: &mut Vec<ChangeError>,
+) where
+    C: Send + Sync,
+    H: WebhookHandler<C> + ?Sized,
+{
+    let entry_meta = EntryMeta {
+        object: "whatsapp_business_account".to_owned(),
+        id: entry.id,
+        uid: None,
+        time: None,
+    };
+
+    for change in entry.changes {
+        let (field, ret) = match change {
+            Whatsapp::Messages(v) => (
+                "messages".to_owned(),
+                handler.on_whatsapp_messages(&entry_meta, v, ctx).await,
+            ),
+            Whatsapp::MessageTemplateStatusUpdate(v) => (
+                "message_template_status_update".to_owned(),
+                handler
+                    .on_whatsapp_message_template_status_update(&entry_meta, v, ctx)
+                    .await,
+            ),
+            Whatsapp::PhoneNumberQualityUpdate(v) => (
+                "phone_number_quality_update".to_owned(),
+                handler
+                    .on_whatsapp_phone_number_quality_update(&entry_meta, v, ctx)
+                    .await,
+            ),
+            Whatsapp::Unknown { field, value } => (
+                field.to_owned(),
+                handler
+                    .on_unknown_change(&entry_meta, field, value, ctx)
+                    .await,
+            ),
+        };
+        push_error(&entry_meta, field, ret, errors);
+    }
+}
+
+fn push_error(
+    entry_meta: &EntryMeta,
+    field: String,
+    ret: Result<(), HandlerError>,
+    errors: &mut Vec<ChangeError>,
+) {
+    if let Err(error) = ret {
+        errors.push(ChangeError {
+            object: entry_meta.object.to_owned(),
+            entry_id: Some(entry_meta.id),
+            field,
+            error,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct MyHandler {
+        comments: AtomicUsize,
+        permissions: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl WebhookHandler<()> for MyHandler {
+        async fn on_instagram_comment(
+            &self,
+            entry_meta: &EntryMeta,
+            value: CommentsValue,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            assert!(entry_meta.is_test());
+            self.comments.fetch_add(1, Ordering::SeqCst);
+            if value.text == "fail" {
+                return Err("comment failed".into());
+            }
+            Ok(())
+        }
+
+        async fn on_permission_changed(
+            &self,
+            _entry_meta: &EntryMeta,
+            permission: FacebookPermission,
+            _value: PermissionsValue,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            assert_eq!(permission, FacebookPermission::AdsRead);
+            self.permissions.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch() {
+        let json = r#"
+        {
+            "object": "instagram",
+            "entry": [
+                {
+                    "id": "0",
+                    "time": 1624005617,
+                    "changes": [
+                        {
+                            "field": "comments",
+                            "value": {
+                                "id": "17865799348089039",
+                                "text": "fail"
+                            }
+                        },
+                        {
+                            "field": "comments",
+                            "value": {
+                                "id": "17865799348089040",
+                                "text": "This is an example."
+                            }
+                        },
+                        {
+                            "field": "foo_bar",
+                            "value": {}
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+        let handler = MyHandler::default();
+        match dispatch(serde_json::from_str(json).unwrap(), &handler, &()).await {
+            Err(DispatchError(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].object, "instagram");
+                assert_eq!(errors[0].entry_id, Some(0));
+                assert_eq!(errors[0].field, "comments");
+                assert_eq!(errors[0].error.to_string(), "comment failed");
+            }
+            Ok(_) => panic!(""),
+        }
+        assert_eq!(handler.comments.load(Ordering::SeqCst), 2);
+
+        let json = r#"
+        {
+            "object": "permissions",
+            "entry": [
+                {
+                    "id": "0",
+                    "uid": "0",
+                    "time": 1624610156,
+                    "changes": [
+                        {
+                            "field": "ads_read",
+                            "value": {
+                                "verb": "granted"
+                            }
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+        let handler = Arc::new(MyHandler::default());
+        let callback = pass_back_callback::<(), _>(handler.clone());
+        callback(serde_json::from_str(json).unwrap(), ())
+            .await
+            .unwrap();
+        assert_eq!(handler.permissions.load(Ordering::SeqCst), 1);
+    }
+
+    #[derive(Default)]
+    struct MessagingHandler {
+        calls: std::sync::Mutex<Vec<String>>,
+    }
+    impl MessagingHandler {
+        fn record(&self, name: &str) -> Result<(), HandlerError> {
+            self.calls.lock().unwrap().push(name.to_owned());
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl WebhookHandler<()> for MessagingHandler {
+        async fn on_messenger_message(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &MessengerMessagingMeta,
+            _value: MessengerMessage,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("messenger_message")
+        }
+        async fn on_messenger_postback(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &MessengerMessagingMeta,
+            _value: MessengerPostback,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("messenger_postback")
+        }
+        async fn on_messenger_delivery(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &MessengerMessagingMeta,
+            _value: MessengerDelivery,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("messenger_delivery")
+        }
+        async fn on_messenger_read(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &MessengerMessagingMeta,
+            _value: MessengerRead,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("messenger_read")
+        }
+        async fn on_messenger_reaction(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &MessengerMessagingMeta,
+            _value: MessengerReaction,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("messenger_reaction")
+        }
+        async fn on_messenger_referral(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &MessengerMessagingMeta,
+            _value: MessengerReferral,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("messenger_referral")
+        }
+        async fn on_messenger_optin(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &MessengerMessagingMeta,
+            _value: MessengerOptin,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("messenger_optin")
+        }
+        async fn on_instagram_message(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &InstagramMessagingMeta,
+            _value: InstagramMessage,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("instagram_message")
+        }
+        async fn on_instagram_reaction(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &InstagramMessagingMeta,
+            _value: InstagramReaction,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("instagram_reaction")
+        }
+        async fn on_instagram_postback(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &InstagramMessagingMeta,
+            _value: InstagramPostback,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("instagram_postback")
+        }
+        async fn on_instagram_read(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &InstagramMessagingMeta,
+            _value: InstagramRead,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("instagram_read")
+        }
+        async fn on_instagram_referral(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &InstagramMessagingMeta,
+            _value: InstagramReferral,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("instagram_referral")
+        }
+        async fn on_instagram_pass_thread_control(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &InstagramMessagingMeta,
+            _value: InstagramThreadControl,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("instagram_pass_thread_control")
+        }
+        async fn on_instagram_take_thread_control(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &InstagramMessagingMeta,
+            _value: InstagramThreadControl,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("instagram_take_thread_control")
+        }
+        async fn on_instagram_request_thread_control(
+            &self,
+            _entry_meta: &EntryMeta,
+            _messaging_meta: &InstagramMessagingMeta,
+            _value: InstagramThreadControl,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record("instagram_request_thread_control")
+        }
+        async fn on_unknown_change(
+            &self,
+            _entry_meta: &EntryMeta,
+            field: String,
+            _value: Value,
+            _ctx: &(),
+        ) -> Result<(), HandlerError> {
+            self.record(&format!("unknown_{field}"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_messaging() {
+        let json = r#"
+        {
+            "object": "page",
+            "entry": [
+                {
+                    "id": "0",
+                    "time": 1458692752478,
+                    "messaging": [
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1458692752478, "message": {"mid": "m_1", "text": "hello"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1458692752478, "postback": {"title": "Start", "payload": "START"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "delivery": {"mids": ["m_1"], "watermark": 1458668856253}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1458692752478, "read": {"watermark": 1458668856253}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1458692752478, "reaction": {"mid": "m_1", "action": "react", "emoji": "👍"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1458692752478, "referral": {"ref": "REF", "source": "SHORTLINK", "type": "OPEN_THREAD"}},
+                        {"sender": {"user_ref": "USER_REF"}, "recipient": {"id": "0"}, "timestamp": 1458692752478, "optin": {"ref": "REF"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1458692752478, "response_feedback": {"mid": "m_1"}}
+                    ]
+                }
+            ]
+        }
+        "#;
+        let handler = MessagingHandler::default();
+        dispatch(serde_json::from_str(json).unwrap(), &handler, &())
+            .await
+            .unwrap();
+        assert_eq!(
+            *handler.calls.lock().unwrap(),
+            vec![
+                "messenger_message",
+                "messenger_postback",
+                "messenger_delivery",
+                "messenger_read",
+                "messenger_reaction",
+                "messenger_referral",
+                "messenger_optin",
+                "unknown_response_feedback",
+            ]
+        );
+
+        let json = r#"
+        {
+            "object": "instagram",
+            "entry": [
+                {
+                    "id": "0",
+                    "time": 1603059201,
+                    "changes": [
+                        {
+                            "field": "messages",
+                            "value": {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": "1603059201", "message": {"mid": "m_0", "text": "hello"}}
+                        }
+                    ],
+                    "messaging": [
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1603059201, "message": {"mid": "m_1", "text": "hello"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1603059201, "reaction": {"mid": "m_1", "action": "react", "reaction": "love"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1603059201, "postback": {"mid": "m_2", "title": "Start", "payload": "START"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1603059201, "read": {"mid": "m_1"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1603059201, "referral": {"ref": "REF", "source": "ADS", "type": "OPEN_THREAD"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1603059201, "pass_thread_control": {"new_owner_app_id": "123"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1603059201, "take_thread_control": {"previous_owner_app_id": "123"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1603059201, "request_thread_control": {"requested_owner_app_id": "123"}},
+                        {"sender": {"id": "1"}, "recipient": {"id": "0"}, "timestamp": 1603059201, "message_edit": {"mid": "m_1", "text": "hi", "num_edit": 1}}
+                    ]
+                }
+            ]
+        }
+        "#;
+        let handler = MessagingHandler::default();
+        dispatch(serde_json::from_str(json).unwrap(), &handler, &())
+            .await
+            .unwrap();
+        assert_eq!(
+            *handler.calls.lock().unwrap(),
+            vec![
+                "instagram_message",
+                "instagram_message",
+                "instagram_reaction",
+                "instagram_postback",
+                "instagram_read",
+                "instagram_referral",
+                "instagram_pass_thread_control",
+                "instagram_take_thread_control",
+                "instagram_request_thread_control",
+                "unknown_message_edit",
+            ]
+        );
+    }
+}
@@ -1,5 +1,14 @@
 //! [Official doc](https://developers.facebook.com/docs/graph-api/webhooks)
 
+pub mod context;
+pub mod dedup;
 pub mod event_notifications;
+pub mod handler;
+#[cfg(feature = "with-queue")]
+pub mod queue;
+#[cfg(feature = "with-spool")]
+pub mod spool;
 pub mod topics;
 pub mod verification_requests;
+
+mod serde_ext;
@@ -0,0 +1,334 @@
+//! Acknowledge right after verifying, then process the [`Payload`] in the background.
+//!
+//! Facebook retries, and eventually disables the subscription, when the response is slow.
+
+use core::time::Duration;
+use std::sync::Arc;
+
+use tokio::{
+    sync::{
+        mpsc::{self, error::TrySendError},
+        oneshot, Semaphore,
+    },
+    task::{JoinError, JoinHandle, JoinSet},
+    time::timeout,
+};
+
+use crate::event_notifications::{PassBackCallbackFn, Payload};
+
+/// Called with the failed [`Payload`] and the error of the callback.
+pub type ErrorCallbackFn<'a> = Box<dyn Fn(Payload, Box<dyn std::error::Error>) + Send + Sync + 'a>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backpressure {
+    /// Fail right away when the queue is full.
+    #[default]
+    Reject,
+    /// Wait up to the duration for a free slot.
+    Wait(Duration),
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkQueueConfig {
+    /// Max payloads waiting, not including the ones being processed.
+    pub capacity: usize,
+    /// Max payloads processed at the same time.
+    pub concurrency: usize,
+    pub backpressure: Backpressure,
+}
+impl Default for WorkQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            concurrency: 16,
+            backpressure: Backpressure::default(),
+        }
+    }
+}
+
+pub struct WorkQueue<C> {
+    sender: mpsc::Sender<(Payload, C)>,
+    backpressure: Backpressure,
+}
+impl<C> Clone for WorkQueue<C> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            backpressure: self.backpressure,
+        }
+    }
+}
+
+impl<C> WorkQueue<C>
+where
+    C: Send + 'static,
+{
+    /// Spawn the workers onto the current tokio runtime.
+    ///
+    /// The errors of `callback` are passed to `error_callback`.
+    pub fn spawn(
+        config: WorkQueueConfig,
+        callback: PassBackCallbackFn<'static, C>,
+        error_callback: Option<ErrorCallbackFn<'static>>,
+    ) -> (Self, WorkQueueHandle) {
+        let (sender, receiver) = mpsc::channel(config.capacity.max(1));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+        let join_handle = tokio::spawn(run(
+            receiver,
+            shutdown_rx,
+            config.concurrency.max(1),
+            Arc::new(callback),
+            error_callback.map(Arc::new),
+        ));
+
+        (
+            Self {
+                sender,
+                backpressure: config.backpressure,
+            },
+            WorkQueueHandle {
+                shutdown_tx,
+                join_handle,
+            },
+        )
+    }
+
+    pub async fn enqueue(&self, payload: Payload, ctx: C) -> Result<(), EnqueueError> {
+        match self.backpressure {
+            Backpressure::Reject => self
+                .sender
+                .try_send((payload, ctx))
+                .map_err(|err| match err {
+                    TrySendError::Full(_) => EnqueueError::Full,
+                    TrySendError::Closed(_) => EnqueueError::Closed,
+                }),
+            Backpressure::Wait(dur) => match timeout(dur, self.sender.send((payload, ctx))).await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(_)) => Err(EnqueueError::Closed),
+                Err(_) => Err(EnqueueError::Full),
+            },
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum EnqueueError {
+    #[error("QueueFull")]
+    Full,
+    #[error("QueueClosed")]
+    Closed,
+}
+
+/// Dropping it doesn't stop the workers.
+#[derive(Debug)]
+pub struct WorkQueueHandle {
+    shutdown_tx: oneshot::Sender<()>,
+    join_handle: JoinHandle<()>,
+}
+impl WorkQueueHandle {
+    /// Stop accepting, then wait for the waiting and the in-flight payloads to be processed.
+    ///
+    /// The ones not processed within `drain_timeout` are aborted.
+    pub async fn shutdown(self, drain_timeout: Duration) -> Result<(), ShutdownError> {
+        let _ = self.shutdown_tx.send(());
+
+        let abort_handle = self.join_handle.abort_handle();
+        match timeout(drain_timeout, self.join_handle).await {
+            Ok(ret) => ret.map_err(ShutdownError::JoinFailed),
+            Err(_) => {
+                abort_handle.abort();
+                Err(ShutdownError::DrainTimeout)
+            }
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ShutdownError {
+    #[error("DrainTimeout")]
+    DrainTimeout,
+    #[error("JoinFailed {0}")]
+    JoinFailed(JoinError),
+}
+
+async fn run<C>(
+    mut receiver: mpsc::Receiver<(Payload, C)>,
+    mut shutdown_rx: oneshot::Receiver<()>,
+    concurrency: usize,
+    callback: Arc<PassBackCallbackFn<'static, C>>,
+    error_callback: Option<Arc<ErrorCallbackFn<'static>>>,
+) where
+    C: Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let mut join_set = JoinSet::new();
+    let mut shutdown_rx_done = false;
+
+    loop {
+        // Acquire before receiving, otherwise one more payload is taken off the channel and waits here.
+        let permit = tokio::select! {
+            permit = semaphore.clone().acquire_owned() => permit.expect("semaphore never closed"),
+            ret = &mut shutdown_rx, if !shutdown_rx_done => {
+                shutdown_rx_done = true;
+                close_on_shutdown(ret, &mut receiver);
+                continue;
+            }
+        };
+        let job = tokio::select! {
+            job = receiver.recv() => job,
+            ret = &mut shutdown_rx, if !shutdown_rx_done => {
+                shutdown_rx_done = true;
+                close_on_shutdown(ret, &mut receiver);
+                continue;
+            }
+        };
+        let Some((payload, ctx)) = job else {
+            break;
+        };
+
+        let callback = callback.clone();
+        let error_callback = error_callback.clone();
+        join_set.spawn(async move {
+            match error_callback {
+                Some(error_callback) => {
+                    if let Err(err) = callback(payload.clone(), ctx).await {
+                        error_callback(payload, err);
+                    }
+                }
+                None => {
+                    let _ = callback(payload, ctx).await;
+                }
+            }
+            drop(permit);
+        });
+
+        while join_set.try_join_next().is_some() {}
+    }
+
+    while join_set.join_next().await.is_some() {}
+}
+
+fn close_on_shutdown<T>(
+    ret: Result<(), oneshot::error::RecvError>,
+    receiver: &mut mpsc::Receiver<T>,
+) {
+    // Err means the handle is dropped.
+    if ret.is_ok() {
+        receiver.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    fn payload() -> Payload {
+        Payload::Other {
+            object: "user".to_owned(),
+            entry: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_work_queue() {
+        let gate = Arc::new(Semaphore::new(0));
+        let processed = Arc::new(AtomicUsize::new(0));
+        let failed = Arc::new(AtomicUsize::new(0));
+
+        let (queue, handle) = WorkQueue::spawn(
+            WorkQueueConfig {
+                capacity: 1,
+                concurrency: 1,
+                backpressure: Backpressure::Reject,
+            },
+            {
+                let gate = gate.clone();
+                let processed = processed.clone();
+                Box::new(move |_payload, ctx: usize| {
+                    let gate = gate.clone();
+                    let processed = processed.clone();
+                    Box::pin(async move {
+                        let _permit = gate.acquire().await;
+                        processed.fetch_add(1, Ordering::SeqCst);
+                        match ctx % 2 {
+                            0 => Ok(()),
+                            _ => Err("odd".into()),
+                        }
+                    })
+                })
+            },
+            {
+                let failed = failed.clone();
+                Some(Box::new(move |payload, err| {
+                    assert!(matches!(payload, Payload::Other { object, .. } if object == "user"));
+                    assert_eq!(err.to_string(), "odd");
+                    failed.fetch_add(1, Ordering::SeqCst);
+                }))
+            },
+        );
+
+        // backpressure
+        let mut accepted = 0;
+        let mut rejected = false;
+        for i in 0..10 {
+            match queue.enqueue(payload(), i).await {
+                Ok(_) => accepted += 1,
+                Err(EnqueueError::Full) => {
+                    rejected = true;
+                    break;
+                }
+                Err(err) => panic!("{}", err),
+            }
+            tokio::task::yield_now().await;
+        }
+        assert!(rejected);
+        // One being processed, `capacity` waiting.
+        assert_eq!(accepted, 2);
+        assert_eq!(processed.load(Ordering::SeqCst), 0);
+
+        let queue_wait = WorkQueue {
+            sender: queue.sender.clone(),
+            backpressure: Backpressure::Wait(Duration::from_millis(10)),
+        };
+        assert_eq!(
+            queue_wait.enqueue(payload(), 100).await,
+            Err(EnqueueError::Full)
+        );
+
+        // drain
+        gate.add_permits(1);
+        handle.shutdown(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(processed.load(Ordering::SeqCst), accepted);
+        assert_eq!(failed.load(Ordering::SeqCst), accepted / 2);
+
+        assert_eq!(
+            queue.enqueue(payload(), 100).await,
+            Err(EnqueueError::Closed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_work_queue_drain_timeout() {
+        let (queue, handle) = WorkQueue::spawn(
+            WorkQueueConfig::default(),
+            Box::new(move |_payload, _ctx: ()| {
+                Box::pin(async move {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    Ok(())
+                })
+            }),
+            None,
+        );
+
+        queue.enqueue(payload(), ()).await.unwrap();
+        tokio::task::yield_now().await;
+
+        match handle.shutdown(Duration::from_millis(10)).await {
+            Err(ShutdownError::DrainTimeout) => {}
+            x => panic!("{:?}", x),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use core::fmt::Display;
+
+use chrono::{DateTime, TimeZone as _, Utc};
+use serde::{
+    de::{Deserializer, Error as _},
+    Serializer,
+};
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+};
+
+/// Number or numeric string, in seconds or milliseconds.
+pub(crate) fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let v: i64 = deserialize_number_from_string(deserializer)?;
+    timestamp_to_datetime(v).ok_or_else(|| D::Error::custom(format!("invalid timestamp {v}")))
+}
+
+pub(crate) fn deserialize_option_timestamp<'de, D>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match deserialize_option_number_from_string::<i64, D>(deserializer)? {
+        Some(v) => timestamp_to_datetime(v)
+            .map(Some)
+            .ok_or_else(|| D::Error::custom(format!("invalid timestamp {v}"))),
+        None => Ok(None),
+    }
+}
+
+/// Seconds, milliseconds when there are any.
+pub(crate) fn serialize_timestamp<S>(v: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if v.timestamp_subsec_millis() == 0 {
+        serializer.serialize_i64(v.timestamp())
+    } else {
+        serializer.serialize_i64(v.timestamp_millis())
+    }
+}
+
+pub(crate) fn serialize_option_timestamp<S>(
+    v: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match v {
+        Some(v) => serialize_timestamp(v, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Facebook sends the ids as strings.
+pub(crate) fn serialize_to_string<T, S>(v: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    serializer.collect_str(v)
+}
+
+pub(crate) fn serialize_option_to_string<T, S>(
+    v: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    match v {
+        Some(v) => serializer.collect_str(v),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn timestamp_to_datetime(v: i64) -> Option<DateTime<Utc>> {
+    // 100_000_000_000 seconds is in the year 5138.
+    if v >= 100_000_000_000 {
+        Utc.timestamp_millis_opt(v).single()
+    } else {
+        Utc.timestamp_opt(v, 0).single()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+
+    #[test]
+    fn test_deserialize_timestamp() {
+        #[derive(Deserialize)]
+        struct Foo {
+            #[serde(deserialize_with = "deserialize_timestamp")]
+            time: DateTime<Utc>,
+        }
+
+        for json in [
+            r#"{"time": 1520544816}"#,
+            r#"{"time": "1520544816"}"#,
+            r#"{"time": 1520544816000}"#,
+        ] {
+            assert_eq!(
+                serde_json::from_str::<Foo>(json).unwrap().time.timestamp(),
+                1520544816
+            );
+        }
+    }
+
+    #[test]
+    fn test_serialize_timestamp() {
+        #[derive(Serialize, Deserialize)]
+        struct Foo {
+            #[serde(
+                deserialize_with = "deserialize_timestamp",
+                serialize_with = "serialize_timestamp"
+            )]
+            time: DateTime<Utc>,
+        }
+
+        for (json, time) in [
+            (r#"{"time":1520544816}"#, 1520544816000),
+            (r#"{"time":1520544816123}"#, 1520544816123),
+        ] {
+            let foo = serde_json::from_str::<Foo>(json).unwrap();
+            assert_eq!(foo.time.timestamp_millis(), time);
+            assert_eq!(serde_json::to_string(&foo).unwrap(), json);
+        }
+    }
+}
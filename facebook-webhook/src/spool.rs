@@ -0,0 +1,830 @@
+//! Append the verified deliveries to a local log before acknowledging, then replay them at least once.
+//!
+//! Layout of `dir`:
+//!
+//! * `{base_offset:020}.log` segments, every record is `len u32 | crc32 u32 | entry`.
+//! * `offset`, the committed offset, entries before it are processed.
+//! * `lock`, locked exclusively while the spool is open.
+
+use core::time::Duration;
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, BufReader, Read, Seek as _, SeekFrom, Write as _},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tokio::sync::Notify;
+
+use crate::event_notifications::{PassBackCallbackFn, Payload};
+
+const SEGMENT_EXTENSION: &str = "log";
+const OFFSET_FILE_NAME: &str = "offset";
+const LOCK_FILE_NAME: &str = "lock";
+const RECORD_HEADER_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FsyncPolicy {
+    /// Sync after every append, nothing acknowledged is lost.
+    #[default]
+    Always,
+    /// Sync after every N appends, up to N-1 acknowledged entries are lost on power failure.
+    EveryN(u32),
+    /// Leave it to the OS, survives process crashes only.
+    Never,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpoolConfig {
+    pub dir: PathBuf,
+    /// Roll to a new segment when the current one reaches it.
+    pub segment_max_bytes: u64,
+    pub fsync: FsyncPolicy,
+    /// Entries read, and fed to the callback, per offset commit of [`Spool::replay`].
+    ///
+    /// Up to that many processed entries are fed again after a crash.
+    pub replay_batch_size: usize,
+}
+impl SpoolConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            segment_max_bytes: 64 * 1024 * 1024,
+            fsync: FsyncPolicy::default(),
+            replay_batch_size: 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpoolEntry {
+    pub offset: u64,
+    pub received_at: SystemTime,
+    pub headers: Vec<(String, Vec<u8>)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Spool {
+    inner: Arc<Mutex<Inner>>,
+    /// Where the previous [`Spool::read_from`] stopped, the next batch seeks to it instead of scanning the segment.
+    cursor: Arc<Mutex<Option<Cursor>>>,
+    appended: Arc<Notify>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    offset: u64,
+    base_offset: u64,
+    position: u64,
+}
+
+#[derive(Debug)]
+struct Inner {
+    config: SpoolConfig,
+    /// Base offsets, sorted.
+    segments: Vec<u64>,
+    writer: File,
+    writer_len: u64,
+    next_offset: u64,
+    unsynced: u32,
+    /// Unlocked when it is closed, i.e. all the clones of the [`Spool`] are dropped.
+    _lock_file: File,
+}
+
+impl Spool {
+    /// Truncate the torn tail of the last segment, if any.
+    ///
+    /// [`SpoolError::Locked`] when `dir` is open by another [`Spool`], in this process or another.
+    pub fn open(config: SpoolConfig) -> Result<Self, SpoolError> {
+        fs::create_dir_all(&config.dir)?;
+
+        let lock_file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(config.dir.join(LOCK_FILE_NAME))?;
+        lock_file.try_lock().map_err(|err| match err {
+            TryLockError::WouldBlock => SpoolError::Locked,
+            TryLockError::Error(err) => SpoolError::Io(err),
+        })?;
+
+        let mut segments = list_segments(&config.dir)?;
+
+        let (writer, writer_len, next_offset) = match segments.last() {
+            Some(base_offset) => {
+                let path = segment_path(&config.dir, *base_offset);
+                let (entries, valid_len) = read_segment(&path, false)?;
+
+                let writer = OpenOptions::new().read(true).write(true).open(&path)?;
+                if writer.metadata()?.len() != valid_len {
+                    writer.set_len(valid_len)?;
+                    writer.sync_all()?;
+                }
+
+                let next_offset = entries.last().map(|x| x.offset + 1).unwrap_or(*base_offset);
+                (writer, valid_len, next_offset)
+            }
+            None => {
+                let base_offset = read_committed_offset(&config.dir)?;
+                segments.push(base_offset);
+                (create_segment(&config.dir, base_offset)?, 0, base_offset)
+            }
+        };
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(Inner {
+                config,
+                segments,
+                writer,
+                writer_len,
+                next_offset,
+                unsynced: 0,
+                _lock_file: lock_file,
+            })),
+            cursor: Default::default(),
+            appended: Arc::new(Notify::new()),
+        })
+    }
+
+    /// Returns the offset of the entry.
+    pub fn append(&self, headers: &[(&str, &[u8])], body: &[u8]) -> Result<u64, SpoolError> {
+        let mut inner = self.lock();
+
+        if inner.writer_len >= inner.config.segment_max_bytes {
+            let base_offset = inner.next_offset;
+            inner.writer.sync_all()?;
+            inner.writer = create_segment(&inner.config.dir, base_offset)?;
+            inner.writer_len = 0;
+            inner.segments.push(base_offset);
+            inner.unsynced = 0;
+        }
+
+        let offset = inner.next_offset;
+        let record = encode_record(offset, SystemTime::now(), headers, body);
+        let writer_len = inner.writer_len;
+        inner.writer.seek(SeekFrom::Start(writer_len))?;
+        inner.writer.write_all(&record)?;
+
+        inner.unsynced += 1;
+        let sync = match inner.config.fsync {
+            FsyncPolicy::Always => true,
+            FsyncPolicy::EveryN(n) => inner.unsynced >= n,
+            FsyncPolicy::Never => false,
+        };
+        if sync {
+            inner.writer.sync_data()?;
+            inner.unsynced = 0;
+        }
+
+        inner.writer_len += record.len() as u64;
+        inner.next_offset += 1;
+        drop(inner);
+
+        self.appended.notify_one();
+
+        Ok(offset)
+    }
+
+    /// Up to `max` entries, starting from `offset`.
+    ///
+    /// The segments are read without holding the lock, so it doesn't block [`Spool::append`].
+    pub fn read_from(&self, offset: u64, max: usize) -> Result<Vec<SpoolEntry>, SpoolError> {
+        let (dir, segments, writer_len) = {
+            let inner = self.lock();
+            (
+                inner.config.dir.clone(),
+                inner.segments.clone(),
+                inner.writer_len,
+            )
+        };
+        let mut cursor = self.cursor.lock().expect("cursor lock poisoned").take();
+
+        let mut ret = vec![];
+        for (i, base_offset) in segments.iter().enumerate() {
+            let end = match segments.get(i + 1) {
+                Some(next_base_offset) if *next_base_offset <= offset => continue,
+                Some(_) => None,
+                // Only the part appended before the snapshot, the rest may be being written.
+                None => Some(writer_len),
+            };
+            if ret.len() >= max {
+                break;
+            }
+
+            let position = match cursor {
+                Some(cursor) if cursor.base_offset == *base_offset && cursor.offset == offset => {
+                    cursor.position
+                }
+                _ => 0,
+            };
+            let path = segment_path(&dir, *base_offset);
+            let mut file = File::open(&path)?;
+            let end = match end {
+                Some(end) => end,
+                None => file.metadata()?.len(),
+            };
+            if position >= end {
+                continue;
+            }
+            file.seek(SeekFrom::Start(position))?;
+
+            let mut reader = BufReader::new(file).take(end - position);
+            let mut position = position;
+            while position < end && ret.len() < max {
+                let (entry, len) =
+                    read_record(&mut reader)?.ok_or(SpoolError::SegmentCorrupted {
+                        base_offset: *base_offset,
+                        position,
+                    })?;
+                position += len as u64;
+                if entry.offset < offset {
+                    continue;
+                }
+
+                cursor = Some(Cursor {
+                    offset: entry.offset + 1,
+                    base_offset: *base_offset,
+                    position,
+                });
+                ret.push(entry);
+            }
+        }
+
+        *self.cursor.lock().expect("cursor lock poisoned") = cursor;
+
+        Ok(ret)
+    }
+
+    pub fn next_offset(&self) -> u64 {
+        self.lock().next_offset
+    }
+
+    pub fn committed_offset(&self) -> Result<u64, SpoolError> {
+        let dir = self.lock().config.dir.clone();
+        read_committed_offset(&dir)
+    }
+
+    /// Mark the entries before `offset` as processed.
+    pub fn commit(&self, offset: u64) -> Result<(), SpoolError> {
+        let inner = self.lock();
+
+        let path = inner.config.dir.join(OFFSET_FILE_NAME);
+        let tmp_path = path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(offset.to_string().as_bytes())?;
+        file.sync_all()?;
+        fs::rename(tmp_path, path)?;
+
+        Ok(())
+    }
+
+    /// Delete the segments whose entries are all before the committed offset, returns how many.
+    pub fn compact(&self) -> Result<usize, SpoolError> {
+        let mut inner = self.lock();
+        let committed_offset = read_committed_offset(&inner.config.dir)?;
+
+        let mut n = 0;
+        while inner.segments.len() > 1 && inner.segments[1] <= committed_offset {
+            let base_offset = inner.segments.remove(0);
+            fs::remove_file(segment_path(&inner.config.dir, base_offset))?;
+            n += 1;
+        }
+
+        Ok(n)
+    }
+
+    /// Resolve when an entry is appended after the previous call.
+    pub async fn appended(&self) {
+        self.appended.notified().await
+    }
+
+    /// Feed the entries from the committed offset to `callback`, the offset is committed after every batch of [`SpoolConfig::replay_batch_size`].
+    ///
+    /// Stop at the first failure, the entries before it are committed, the failed entry is fed again by the next replay.
+    /// The entries whose body isn't a [`Payload`] are committed past and returned, they'd never succeed.
+    pub async fn replay<C: Clone>(
+        &self,
+        ctx: C,
+        callback: &PassBackCallbackFn<'_, C>,
+    ) -> Result<Replayed, ReplayError> {
+        let mut offset = blocking(self.clone(), |spool| spool.committed_offset()).await?;
+        let batch_size = self.lock().config.replay_batch_size.max(1);
+        let mut replayed = Replayed::default();
+
+        loop {
+            let entries = blocking(self.clone(), move |spool| {
+                spool.read_from(offset, batch_size)
+            })
+            .await?;
+            if entries.is_empty() {
+                return Ok(replayed);
+            }
+
+            let batch_offset = offset;
+            let mut ret = Ok(());
+            for entry in entries {
+                let next_offset = entry.offset + 1;
+                match serde_json::from_slice::<Payload>(&entry.body) {
+                    Ok(payload) => match callback(payload, ctx.clone()).await {
+                        Ok(_) => replayed.processed += 1,
+                        Err(err) => {
+                            ret = Err(ReplayError::CallbackFailed {
+                                offset: entry.offset,
+                                error: err.to_string(),
+                            });
+                            break;
+                        }
+                    },
+                    Err(err) => replayed.skipped.push(SkippedEntry { entry, error: err }),
+                }
+                offset = next_offset;
+            }
+
+            if offset > batch_offset {
+                blocking(self.clone(), move |spool| spool.commit(offset)).await?;
+            }
+            ret?;
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().expect("spool lock poisoned")
+    }
+}
+
+/// All the entries in `dir`, without opening the spool, e.g. to inspect it while it's being appended.
+///
+/// The torn tail of the last segment is skipped.
+pub fn read_entries(dir: impl AsRef<Path>) -> Result<Vec<SpoolEntry>, SpoolError> {
+    let segments = list_segments(dir.as_ref())?;
+
+    let mut ret = vec![];
+    for (i, base_offset) in segments.iter().enumerate() {
+        let is_last = i + 1 == segments.len();
+        let (entries, _) = read_segment(&segment_path(dir.as_ref(), *base_offset), !is_last)?;
+        ret.extend(entries);
+    }
+    Ok(ret)
+}
+
+/// Run the file operations out of the async runtime.
+pub(crate) async fn blocking<T, F>(spool: Spool, f: F) -> Result<T, SpoolError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Spool) -> Result<T, SpoolError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || f(&spool))
+        .await
+        .map_err(|err| SpoolError::Io(io::Error::other(err)))?
+}
+
+#[derive(Debug, Default)]
+pub struct Replayed {
+    pub processed: usize,
+    /// The dead letters, the caller may keep them elsewhere.
+    pub skipped: Vec<SkippedEntry>,
+}
+
+#[derive(Debug)]
+pub struct SkippedEntry {
+    pub entry: SpoolEntry,
+    pub error: serde_json::Error,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SpoolError {
+    #[error("Io {0}")]
+    Io(#[from] io::Error),
+    #[error("SegmentCorrupted {base_offset} at {position}")]
+    SegmentCorrupted { base_offset: u64, position: u64 },
+    #[error("Locked")]
+    Locked,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    #[error("Spool {0}")]
+    Spool(#[from] SpoolError),
+    #[error("CallbackFailed {offset} {error}")]
+    CallbackFailed { offset: u64, error: String },
+}
+
+//
+fn segment_path(dir: &Path, base_offset: u64) -> PathBuf {
+    dir.join(format!("{:020}.{}", base_offset, SEGMENT_EXTENSION))
+}
+
+/// Base offsets, sorted.
+fn list_segments(dir: &Path) -> Result<Vec<u64>, SpoolError> {
+    let mut segments = vec![];
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        if path.extension().and_then(|x| x.to_str()) != Some(SEGMENT_EXTENSION) {
+            continue;
+        }
+        if let Some(base_offset) = path
+            .file_stem()
+            .and_then(|x| x.to_str())
+            .and_then(|x| x.parse::<u64>().ok())
+        {
+            segments.push(base_offset);
+        }
+    }
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+fn create_segment(dir: &Path, base_offset: u64) -> Result<File, SpoolError> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(segment_path(dir, base_offset))?;
+    // Persist the directory entry of the new file.
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(file)
+}
+
+fn read_committed_offset(dir: &Path) -> Result<u64, SpoolError> {
+    match fs::read_to_string(dir.join(OFFSET_FILE_NAME)) {
+        Ok(s) => s.trim().parse().map_err(|_| {
+            SpoolError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "offset file invalid",
+            ))
+        }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Returns the entries and the length of the valid part.
+///
+/// A torn record is the end of the segment, or an error when `strict`.
+fn read_segment(path: &Path, strict: bool) -> Result<(Vec<SpoolEntry>, u64), SpoolError> {
+    let mut bytes = vec![];
+    File::open(path)?.read_to_end(&mut bytes)?;
+
+    let mut entries = vec![];
+    let mut position = 0;
+    while position < bytes.len() {
+        match decode_record(&bytes[position..]) {
+            Some((entry, len)) => {
+                entries.push(entry);
+                position += len;
+            }
+            None => {
+                if strict {
+                    let base_offset = path
+                        .file_stem()
+                        .and_then(|x| x.to_str())
+                        .and_then(|x| x.parse().ok())
+                        .unwrap_or_default();
+                    return Err(SpoolError::SegmentCorrupted {
+                        base_offset,
+                        position: position as u64,
+                    });
+                }
+                break;
+            }
+        }
+    }
+
+    Ok((entries, position as u64))
+}
+
+fn encode_record(
+    offset: u64,
+    received_at: SystemTime,
+    headers: &[(&str, &[u8])],
+    body: &[u8],
+) -> Vec<u8> {
+    let received_at = received_at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+
+    let mut entry = vec![];
+    entry.extend_from_slice(&offset.to_le_bytes());
+    entry.extend_from_slice(&received_at.to_le_bytes());
+    entry.extend_from_slice(&(headers.len() as u32).to_le_bytes());
+    for (name, value) in headers {
+        entry.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        entry.extend_from_slice(name.as_bytes());
+        entry.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        entry.extend_from_slice(value);
+    }
+    entry.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    entry.extend_from_slice(body);
+
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + entry.len());
+    record.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+    record.extend_from_slice(&crc32fast::hash(&entry).to_le_bytes());
+    record.extend_from_slice(&entry);
+    record
+}
+
+/// Returns the entry and the length of the record, `None` when it is torn or corrupted.
+fn decode_record(bytes: &[u8]) -> Option<(SpoolEntry, usize)> {
+    let mut reader = Reader(bytes);
+    let len = reader.u32()? as usize;
+    let crc = reader.u32()?;
+    let entry = reader.bytes(len)?;
+    if crc32fast::hash(entry) != crc {
+        return None;
+    }
+
+    decode_entry(entry).map(|entry| (entry, RECORD_HEADER_LEN + len))
+}
+
+/// Same as [`decode_record`], but read the next record from `reader`.
+fn read_record(reader: &mut impl Read) -> Result<Option<(SpoolEntry, usize)>, SpoolError> {
+    let mut header = [0; RECORD_HEADER_LEN];
+    match reader.read_exact(&mut header) {
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let mut header = Reader(&header);
+    let len = header.u32().expect("4 bytes") as usize;
+    let crc = header.u32().expect("4 bytes");
+
+    let mut entry = vec![];
+    reader.take(len as u64).read_to_end(&mut entry)?;
+    if entry.len() != len || crc32fast::hash(&entry) != crc {
+        return Ok(None);
+    }
+
+    Ok(decode_entry(&entry).map(|entry| (entry, RECORD_HEADER_LEN + len)))
+}
+
+fn decode_entry(entry: &[u8]) -> Option<SpoolEntry> {
+    let mut reader = Reader(entry);
+    let offset = reader.u64()?;
+    let received_at = UNIX_EPOCH + Duration::from_millis(reader.u64()?);
+    let mut headers = vec![];
+    for _ in 0..reader.u32()? {
+        let name_len = reader.u32()? as usize;
+        let name = String::from_utf8(reader.bytes(name_len)?.to_vec()).ok()?;
+        let value_len = reader.u32()? as usize;
+        let value = reader.bytes(value_len)?.to_vec();
+        headers.push((name, value));
+    }
+    let body_len = reader.u32()? as usize;
+    let body = reader.bytes(body_len)?.to_vec();
+
+    Some(SpoolEntry {
+        offset,
+        received_at,
+        headers,
+        body,
+    })
+}
+
+struct Reader<'a>(&'a [u8]);
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|x| u32::from_le_bytes(x.try_into().expect("4 bytes")))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.bytes(8)
+            .map(|x| u64::from_le_bytes(x.try_into().expect("8 bytes")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "facebook-webhook-spool-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = test_dir("append_and_read");
+        let config = SpoolConfig {
+            segment_max_bytes: 1,
+            ..SpoolConfig::new(&dir)
+        };
+
+        let spool = Spool::open(config.clone()).unwrap();
+        match Spool::open(config.clone()) {
+            Err(SpoolError::Locked) => {}
+            x => panic!("{:?}", x),
+        }
+        assert_eq!(
+            spool
+                .append(&[("X-Hub-Signature-256", b"sha256=00")], b"a")
+                .unwrap(),
+            0
+        );
+        assert_eq!(spool.append(&[], b"b").unwrap(), 1);
+        drop(spool);
+
+        // reopen
+        let spool = Spool::open(config).unwrap();
+        assert_eq!(spool.next_offset(), 2);
+        assert_eq!(spool.append(&[], b"c").unwrap(), 2);
+
+        let entries = spool.read_from(0, 10).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0].headers,
+            vec![("X-Hub-Signature-256".to_owned(), b"sha256=00".to_vec())]
+        );
+        assert_eq!(entries[2].body, b"c");
+        assert_eq!(spool.read_from(1, 1).unwrap()[0].body, b"b");
+        // 3 segments and the lock file.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+
+        // torn tail
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(segment_path(&dir, 2))
+            .unwrap();
+        file.write_all(&[1, 2, 3]).unwrap();
+        drop(file);
+        assert_eq!(read_entries(&dir).unwrap().len(), 3);
+        drop(spool);
+
+        let spool = Spool::open(SpoolConfig::new(&dir)).unwrap();
+        assert_eq!(spool.next_offset(), 3);
+        assert_eq!(spool.append(&[], b"d").unwrap(), 3);
+        assert_eq!(spool.read_from(2, 10).unwrap().len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_from_in_batches() {
+        let dir = test_dir("read_from_in_batches");
+        let spool = Spool::open(SpoolConfig {
+            segment_max_bytes: 100,
+            ..SpoolConfig::new(&dir)
+        })
+        .unwrap();
+
+        for i in 0..10_u8 {
+            spool.append(&[], &[i; 20]).unwrap();
+        }
+
+        let mut offset = 0;
+        let mut offsets = vec![];
+        loop {
+            let entries = spool.read_from(offset, 3).unwrap();
+            if entries.is_empty() {
+                break;
+            }
+            assert!(entries.len() <= 3);
+            offset = entries.last().unwrap().offset + 1;
+            offsets.extend(entries.iter().map(|x| x.offset));
+
+            if offset == 6 {
+                spool.append(&[], b"k").unwrap();
+            }
+        }
+        assert_eq!(offsets, (0..11).collect::<Vec<_>>());
+
+        // not from the cursor
+        assert_eq!(spool.read_from(4, 1).unwrap()[0].body, [4; 20]);
+        assert_eq!(spool.read_from(5, 1).unwrap()[0].body, [5; 20]);
+        assert_eq!(spool.read_from(1, 1).unwrap()[0].body, [1; 20]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay() {
+        let dir = test_dir("replay");
+        let spool = Spool::open(SpoolConfig {
+            segment_max_bytes: 1,
+            ..SpoolConfig::new(&dir)
+        })
+        .unwrap();
+
+        for _ in 0..3 {
+            spool
+                .append(&[], br#"{"object":"user","entry":[]}"#)
+                .unwrap();
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let callback: PassBackCallbackFn<'_, ()> = {
+            let calls = calls.clone();
+            Box::new(move |_payload, _ctx| {
+                let n = calls.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    if n == 1 {
+                        Err("failed once".into())
+                    } else {
+                        Ok(())
+                    }
+                })
+            })
+        };
+
+        match spool.replay((), &callback).await {
+            Err(ReplayError::CallbackFailed { offset: 1, .. }) => {}
+            x => panic!("{:?}", x),
+        }
+        assert_eq!(spool.committed_offset().unwrap(), 1);
+
+        assert_eq!(spool.replay((), &callback).await.unwrap().processed, 2);
+        assert_eq!(spool.committed_offset().unwrap(), 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(spool.replay((), &callback).await.unwrap().processed, 0);
+
+        assert_eq!(spool.compact().unwrap(), 2);
+        assert_eq!(
+            spool
+                .read_from(0, 10)
+                .unwrap()
+                .iter()
+                .map(|x| x.offset)
+                .collect::<Vec<_>>(),
+            vec![2]
+        );
+
+        // dead letter
+        spool.append(&[], b"not json").unwrap();
+        spool
+            .append(&[], br#"{"object":"user","entry":[]}"#)
+            .unwrap();
+        let replayed = spool.replay((), &callback).await.unwrap();
+        assert_eq!(replayed.processed, 1);
+        assert_eq!(replayed.skipped.len(), 1);
+        assert_eq!(replayed.skipped[0].entry.offset, 3);
+        assert_eq!(replayed.skipped[0].entry.body, b"not json");
+        assert_eq!(spool.committed_offset().unwrap(), 5);
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_in_batches() {
+        let dir = test_dir("replay_in_batches");
+        let spool = Spool::open(SpoolConfig {
+            replay_batch_size: 2,
+            ..SpoolConfig::new(&dir)
+        })
+        .unwrap();
+
+        for _ in 0..5 {
+            spool
+                .append(&[], br#"{"object":"user","entry":[]}"#)
+                .unwrap();
+        }
+
+        // The committed offset seen by every call.
+        let committed_offsets = Arc::new(Mutex::new(vec![]));
+        let callback: PassBackCallbackFn<'_, Spool> = {
+            let committed_offsets = committed_offsets.clone();
+            Box::new(move |_payload, spool| {
+                committed_offsets
+                    .lock()
+                    .unwrap()
+                    .push(spool.committed_offset().unwrap());
+                Box::pin(async move { Ok(()) })
+            })
+        };
+
+        assert_eq!(
+            spool
+                .replay(spool.clone(), &callback)
+                .await
+                .unwrap()
+                .processed,
+            5
+        );
+        assert_eq!(*committed_offsets.lock().unwrap(), vec![0, 0, 2, 2, 4]);
+        assert_eq!(spool.committed_offset().unwrap(), 5);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,50 +2,122 @@
 //!
 //! Require [Enable Page Subscriptions](https://developers.facebook.com/docs/instagram-api/guides/webhooks#step-2--enable-page-subscriptions)
 
-use serde::Deserialize;
-use serde_aux::field_attributes::deserialize_number_from_string;
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+};
 use serde_json::Value;
 
-#[derive(Deserialize, Debug, Clone)]
+use super::instagram_messaging::Messaging;
+use crate::serde_ext::{serialize_option_to_string, serialize_to_string};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(tag = "field", content = "value", rename_all = "snake_case")]
 pub enum Instagram {
     Comments(CommentsValue),
+    LiveComments(CommentsValue),
     Mentions(MentionsValue),
+    MessageReactions(Messaging),
+    Messages(Messaging),
+    MessagingHandover(Messaging),
+    MessagingPostbacks(Messaging),
+    MessagingReferral(Messaging),
+    MessagingSeen(Messaging),
+    Standby(Messaging),
     StoryInsights(StoryInsightsValue),
+    /// Unknown field, or a value this crate can't parse yet.
+    #[serde(untagged)]
+    Unknown {
+        field: String,
+        value: Value,
+    },
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct CommentsValue {
     /// id == [IG Comment id](https://developers.facebook.com/docs/instagram-api/reference/ig-comment)
-    #[serde(deserialize_with = "deserialize_number_from_string")]
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
     pub id: u64,
     pub text: String,
+    pub from: Option<CommentsFrom>,
+    /// Only when the comment is a reply.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub parent_id: Option<u64>,
     /// Click "Test" in facebook webhooks configure page, it's always None.
     /// Bug in [doc page](https://developers.facebook.com/docs/graph-api/webhooks/reference/instagram/v11.0#fields) , it's not None.
-    pub media: Option<Value>,
+    pub media: Option<CommentsMedia>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CommentsFrom {
+    /// id == [IG User id](https://developers.facebook.com/docs/instagram-api/reference/ig-user) or IGSID
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+    pub username: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CommentsMedia {
+    /// id == [IG Media id](https://developers.facebook.com/docs/instagram-api/reference/ig-media)
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+    pub media_product_type: Option<MediaProductType>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MediaProductType {
+    Ad,
+    Feed,
+    Reels,
+    Story,
+    #[serde(other)]
+    Other,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct MentionsValue {
     /// media_id == [IG Media id](https://developers.facebook.com/docs/instagram-api/reference/ig-media)
-    #[serde(deserialize_with = "deserialize_number_from_string")]
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
     pub media_id: u64,
     /// comment_id == [IG Comment id](https://developers.facebook.com/docs/instagram-api/reference/ig-comment)
-    #[serde(deserialize_with = "deserialize_number_from_string")]
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
     pub comment_id: u64,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct StoryInsightsValue {
     /// media_id == [IG Media id](https://developers.facebook.com/docs/instagram-api/reference/ig-media)
-    #[serde(deserialize_with = "deserialize_number_from_string")]
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
     pub media_id: u64,
     /// metrics == ["Story Metrics"](https://developers.facebook.com/docs/instagram-api/reference/ig-media/insights)
     #[serde(flatten)]
     pub metrics: StoryInsightsMetrics,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct StoryInsightsMetrics {
     pub impressions: isize,
     pub reach: isize,
@@ -104,5 +176,112 @@ mod tests {
             Ok(v) => panic!("{:?}", v),
             Err(err) => panic!("{}", err),
         }
+
+        let json = r#"
+        {
+            "field": "live_comments",
+            "value": {
+                "from": {
+                    "id": "232323232",
+                    "username": "test"
+                },
+                "media": {
+                    "id": "123123123",
+                    "media_product_type": "REELS"
+                },
+                "id": "17865799348089039",
+                "parent_id": "1231231234",
+                "text": "This is an example."
+            }
+        }
+        "#;
+        match serde_json::from_str::<Instagram>(json) {
+            Ok(Instagram::LiveComments(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.from.unwrap().username, "test");
+                assert_eq!(v.parent_id, Some(1231231234));
+                let media = v.media.unwrap();
+                assert_eq!(media.id, 123123123);
+                assert_eq!(media.media_product_type, Some(MediaProductType::Reels));
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "messages",
+            "value": {
+                "sender": {
+                    "id": "12334"
+                },
+                "recipient": {
+                    "id": "23245"
+                },
+                "timestamp": "1527459824",
+                "message": {
+                    "mid": "random_mid",
+                    "text": "random_text"
+                }
+            }
+        }
+        "#;
+        match serde_json::from_str::<Instagram>(json) {
+            Ok(Instagram::Messages(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.sender.id, Some(12334));
+                assert_eq!(v.event.field(), "messages");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "messaging_handover",
+            "value": {
+                "sender": {
+                    "id": "12334"
+                },
+                "recipient": {
+                    "id": "23245"
+                },
+                "timestamp": "1527459824",
+                "pass_thread_control": {
+                    "previous_owner_app_id": "1517776481860111",
+                    "new_owner_app_id": "123456789",
+                    "metadata": "Additional content that the caller wants to set"
+                }
+            }
+        }
+        "#;
+        match serde_json::from_str::<Instagram>(json) {
+            Ok(Instagram::MessagingHandover(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.event.field(), "messaging_handover");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "foo_bar",
+            "value": {
+                "id": "17865799348089039"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Instagram>(json) {
+            Ok(Instagram::Unknown { field, value }) => {
+                assert_eq!(field, "foo_bar");
+                assert_eq!(value["id"], "17865799348089039");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
     }
 }
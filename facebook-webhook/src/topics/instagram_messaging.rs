@@ -0,0 +1,248 @@
+//! [Official doc](https://developers.facebook.com/docs/messenger-platform/instagram/features/webhook)
+//!
+//! Require [Page Subscribed Apps](https://developers.facebook.com/docs/graph-api/reference/page/subscribed_apps#Creating)
+
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::deserialize_option_number_from_string;
+use serde_json::Value;
+
+use super::messaging::unknown_event_field;
+pub use super::messaging::{
+    Attachment, AttachmentPayload, AttachmentType, Message, Participant, Postback, QuickReply,
+    Reaction, ReactionAction, Referral, ReplyTo, StoryReplyTo,
+};
+use crate::serde_ext::serialize_option_to_string;
+
+pub type Messaging = super::messaging::Messaging<MessagingEvent>;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum MessagingEvent {
+    Message(Message),
+    Reaction(Reaction),
+    Postback(Postback),
+    Read(Read),
+    Referral(Referral),
+    PassThreadControl(ThreadControl),
+    TakeThreadControl(ThreadControl),
+    RequestThreadControl(ThreadControl),
+    /// Unknown event, or a value this crate can't parse yet.
+    #[serde(untagged)]
+    Unknown(Value),
+}
+impl MessagingEvent {
+    /// The webhook field this event is subscribed by.
+    pub fn field(&self) -> &str {
+        match self {
+            Self::Message(_) => "messages",
+            Self::Reaction(_) => "message_reactions",
+            Self::Postback(_) => "messaging_postbacks",
+            Self::Read(_) => "messaging_seen",
+            Self::Referral(_) => "messaging_referral",
+            Self::PassThreadControl(_)
+            | Self::TakeThreadControl(_)
+            | Self::RequestThreadControl(_) => "messaging_handover",
+            Self::Unknown(value) => unknown_event_field(value),
+        }
+    }
+}
+
+/// Seen event.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Read {
+    pub mid: String,
+}
+
+/// [Official doc](https://developers.facebook.com/docs/messenger-platform/instagram/features/handover-protocol)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ThreadControl {
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub previous_owner_app_id: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub new_owner_app_id: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub requested_owner_app_id: Option<u64>,
+    pub metadata: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_de() {
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "17841405309211844"
+            },
+            "timestamp": 1603059201,
+            "message": {
+                "mid": "aWdfZAG1faXRlbToxOklHTWVzc2FnZAUlEOjE3ODQxNDA1MzA5MjExODQ0",
+                "text": "Nice story!",
+                "reply_to": {
+                    "story": {
+                        "url": "https://lookaside.fbsbx.com/ig_messaging_cdn/?asset_id=1",
+                        "id": "17890364858150183"
+                    }
+                }
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                timestamp,
+                event: MessagingEvent::Message(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert_eq!(timestamp.unwrap().timestamp(), 1603059201);
+                assert_eq!(v.story_reply().unwrap().id, 17890364858150183);
+                assert!(!v.is_story_mention());
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "17841405309211844"
+            },
+            "timestamp": 1603059201000,
+            "message": {
+                "mid": "aWdfZAG1faXRlbToxOklHTWVzc2FnZAUlEOjE3ODQxNDA1MzA5MjExODQ0",
+                "attachments": [
+                    {
+                        "type": "story_mention",
+                        "payload": {
+                            "url": "https://lookaside.fbsbx.com/ig_messaging_cdn/?asset_id=1"
+                        }
+                    }
+                ]
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                event: MessagingEvent::Message(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert!(v.is_story_mention());
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "17841405309211844"
+            },
+            "timestamp": 1603059201,
+            "read": {
+                "mid": "aWdfZAG1faXRlbToxOklHTWVzc2FnZAUlEOjE3ODQxNDA1MzA5MjExODQ0"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                event: event @ MessagingEvent::Read(_),
+                ..
+            }) => {
+                println!("{:?}", event);
+
+                assert_eq!(event.field(), "messaging_seen");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "17841405309211844"
+            },
+            "timestamp": 1603059201,
+            "reaction": {
+                "mid": "aWdfZAG1faXRlbToxOklHTWVzc2FnZAUlEOjE3ODQxNDA1MzA5MjExODQ0",
+                "action": "react",
+                "reaction": "love",
+                "emoji": "❤️"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                event: MessagingEvent::Reaction(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.action, ReactionAction::React);
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "17841405309211844"
+            },
+            "timestamp": 1603059201000,
+            "message_edit": {"mid": "aWdfZAG1faXRlbToxOklHTWVzc2FnZAUlEOjE3ODQxNDA1MzA5MjExODQ0", "text": "Nice story!!", "num_edit": 1}
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                event: MessagingEvent::Unknown(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert_eq!(MessagingEvent::Unknown(v).field(), "message_edit");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let event = MessagingEvent::Unknown(serde_json::json!({
+            "sender": {"id": "1254459154682919"},
+            "recipient": {"id": "17841405309211844"},
+            "timestamp": 1603059201000_u64,
+            "message_edit": {}
+        }));
+        assert_eq!(event.field(), "message_edit");
+    }
+}
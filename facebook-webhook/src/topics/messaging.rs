@@ -0,0 +1,252 @@
+//! Shared by [`super::messenger`] and [`super::instagram_messaging`], both send `entry[].messaging[]` events.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+};
+use serde_json::Value;
+
+use crate::serde_ext::{
+    deserialize_option_timestamp, serialize_option_timestamp, serialize_option_to_string,
+    serialize_to_string,
+};
+
+/// Item of `entry[].messaging[]`, the event type is the key present besides `sender`, `recipient` and `timestamp`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Messaging<E> {
+    pub sender: Participant,
+    pub recipient: Participant,
+    /// Milliseconds in `entry[].messaging[]`, seconds in `entry[].changes[]`. Missing in some `message_deliveries` events.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_timestamp",
+        serialize_with = "serialize_option_timestamp"
+    )]
+    pub timestamp: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub event: E,
+}
+
+/// The key of the unknown event.
+pub(crate) fn unknown_event_field(value: &Value) -> &str {
+    value
+        .as_object()
+        .and_then(|map| {
+            map.keys()
+                .find(|key| !matches!(key.as_str(), "sender" | "recipient" | "timestamp"))
+        })
+        .map(|key| key.as_str())
+        .unwrap_or_default()
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Participant {
+    /// id == [PSID](https://developers.facebook.com/docs/messenger-platform/identity/user-profile), [IGSID](https://developers.facebook.com/docs/messenger-platform/instagram/features/user-profile), Page id or IG User id
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub id: Option<u64>,
+    /// Only in `messaging_optins` events of the Checkbox Plugin.
+    pub user_ref: Option<String>,
+}
+
+/// [Official doc](https://developers.facebook.com/docs/messenger-platform/reference/webhook-events/messages)
+///
+/// Also for [message_echoes](https://developers.facebook.com/docs/messenger-platform/reference/webhook-events/message-echoes)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Message {
+    pub mid: String,
+    pub text: Option<String>,
+    pub attachments: Option<Vec<Attachment>>,
+    pub quick_reply: Option<QuickReply>,
+    pub reply_to: Option<ReplyTo>,
+    /// Only when the message is sent from an ad.
+    pub referral: Option<Referral>,
+    #[serde(default)]
+    pub is_echo: bool,
+    /// Only in `message_echoes` events.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub app_id: Option<u64>,
+    /// Only in `message_echoes` events.
+    pub metadata: Option<String>,
+    /// Only on Instagram.
+    #[serde(default)]
+    pub is_deleted: bool,
+    /// Only on Instagram.
+    #[serde(default)]
+    pub is_unsupported: bool,
+}
+impl Message {
+    pub fn is_story_mention(&self) -> bool {
+        self.attachments
+            .as_ref()
+            .map(|attachments| {
+                attachments
+                    .iter()
+                    .any(|attachment| attachment.r#type == AttachmentType::StoryMention)
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn story_reply(&self) -> Option<&StoryReplyTo> {
+        self.reply_to.as_ref().and_then(|x| x.story.as_ref())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Attachment {
+    #[serde(rename = "type")]
+    pub r#type: AttachmentType,
+    pub payload: Option<AttachmentPayload>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentType {
+    Audio,
+    File,
+    Image,
+    Video,
+    Location,
+    Template,
+    Fallback,
+    /// Only on Instagram.
+    Share,
+    /// Only on Instagram.
+    StoryMention,
+    /// Only on Instagram.
+    Reel,
+    /// Only on Instagram.
+    IgReel,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AttachmentPayload {
+    /// For `story_mention`, the CDN url of the story, it expires.
+    pub url: Option<String>,
+    pub title: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub sticker_id: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub reel_video_id: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct QuickReply {
+    pub payload: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReplyTo {
+    /// Only when replying to a message.
+    pub mid: Option<String>,
+    /// Only when replying to an Instagram story.
+    pub story: Option<StoryReplyTo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct StoryReplyTo {
+    pub url: String,
+    /// id == [IG Media id](https://developers.facebook.com/docs/instagram-api/reference/ig-media)
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+}
+
+/// [Official doc](https://developers.facebook.com/docs/messenger-platform/reference/webhook-events/messaging_postbacks)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Postback {
+    pub mid: Option<String>,
+    pub title: Option<String>,
+    pub payload: Option<String>,
+    pub referral: Option<Referral>,
+}
+
+/// [Official doc](https://developers.facebook.com/docs/messenger-platform/reference/webhook-events/message-reactions)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Reaction {
+    pub mid: String,
+    pub action: ReactionAction,
+    /// Only when `action` is `react`.
+    pub reaction: Option<String>,
+    pub emoji: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionAction {
+    React,
+    Unreact,
+}
+
+/// [Official doc](https://developers.facebook.com/docs/messenger-platform/reference/webhook-events/messaging_referrals)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Referral {
+    #[serde(rename = "ref")]
+    pub r#ref: Option<String>,
+    pub source: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub ad_id: Option<u64>,
+    pub referer_uri: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_de() {
+        // Messenger, milliseconds.
+        let json = r#"{"sender":{"id":"1254459154682919"},"recipient":{"id":"682498171943165"},"timestamp":1502905976963,"standby":{}}"#;
+        match serde_json::from_str::<Messaging<Value>>(json) {
+            Ok(v) => {
+                assert_eq!(v.sender.id, Some(1254459154682919));
+                assert_eq!(v.timestamp.unwrap().timestamp_millis(), 1502905976963);
+                assert_eq!(unknown_event_field(&v.event), "standby");
+            }
+            Err(err) => panic!("{}", err),
+        }
+
+        // Instagram changes, seconds as a string.
+        let json = r#"{"sender":{"id":"12334"},"recipient":{"id":"23245"},"timestamp":"1527459824","standby":{}}"#;
+        match serde_json::from_str::<Messaging<Value>>(json) {
+            Ok(v) => assert_eq!(v.timestamp.unwrap().timestamp(), 1527459824),
+            Err(err) => panic!("{}", err),
+        }
+
+        // Checkbox Plugin, no id.
+        let json = r#"{"sender":{"user_ref":"UNIQUE_REF_PARAM"},"recipient":{"id":"682498171943165"},"timestamp":1502905976963,"optin":{}}"#;
+        match serde_json::from_str::<Messaging<Value>>(json) {
+            Ok(v) => {
+                assert!(v.sender.id.is_none());
+                assert_eq!(v.sender.user_ref.as_deref(), Some("UNIQUE_REF_PARAM"));
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! [Official doc](https://developers.facebook.com/docs/messenger-platform/reference/webhook-events)
+//!
+//! Require [Page Subscribed Apps](https://developers.facebook.com/docs/graph-api/reference/page/subscribed_apps#Creating)
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::messaging::unknown_event_field;
+pub use super::messaging::{
+    Attachment, AttachmentPayload, AttachmentType, Message, Participant, Postback, QuickReply,
+    Reaction, ReactionAction, Referral, ReplyTo,
+};
+use crate::serde_ext::{deserialize_timestamp, serialize_timestamp};
+
+pub type Messaging = super::messaging::Messaging<MessagingEvent>;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum MessagingEvent {
+    Message(Message),
+    Postback(Postback),
+    Delivery(Delivery),
+    Read(Read),
+    Reaction(Reaction),
+    Referral(Referral),
+    Optin(Optin),
+    /// Unknown event, e.g. `standby`, or a value this crate can't parse yet.
+    #[serde(untagged)]
+    Unknown(Value),
+}
+impl MessagingEvent {
+    /// The webhook field this event is subscribed by.
+    pub fn field(&self) -> &str {
+        match self {
+            Self::Message(message) if message.is_echo => "message_echoes",
+            Self::Message(_) => "messages",
+            Self::Postback(_) => "messaging_postbacks",
+            Self::Delivery(_) => "message_deliveries",
+            Self::Read(_) => "message_reads",
+            Self::Reaction(_) => "message_reactions",
+            Self::Referral(_) => "messaging_referrals",
+            Self::Optin(_) => "messaging_optins",
+            Self::Unknown(value) => unknown_event_field(value),
+        }
+    }
+}
+
+/// [Official doc](https://developers.facebook.com/docs/messenger-platform/reference/webhook-events/message-deliveries)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Delivery {
+    pub mids: Option<Vec<String>>,
+    #[serde(
+        deserialize_with = "deserialize_timestamp",
+        serialize_with = "serialize_timestamp"
+    )]
+    pub watermark: DateTime<Utc>,
+}
+
+/// [Official doc](https://developers.facebook.com/docs/messenger-platform/reference/webhook-events/message-reads)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Read {
+    #[serde(
+        deserialize_with = "deserialize_timestamp",
+        serialize_with = "serialize_timestamp"
+    )]
+    pub watermark: DateTime<Utc>,
+}
+
+/// [Official doc](https://developers.facebook.com/docs/messenger-platform/reference/webhook-events/messaging_optins)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Optin {
+    #[serde(rename = "ref")]
+    pub r#ref: Option<String>,
+    pub user_ref: Option<String>,
+    #[serde(rename = "type")]
+    pub r#type: Option<String>,
+    pub payload: Option<String>,
+    pub notification_messages_token: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone as _;
+
+    #[test]
+    fn test_de() {
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "682498171943165"
+            },
+            "timestamp": 1502905976963,
+            "message": {
+                "mid": "m_AG5Hz2Uq7tuwNEhXfYYKj8mJEM_QPpz5jdCK48PnKAjSdjfipqxqMvK8ma6AC8fplwlqLP_5cgXIbu7I3rBN0P",
+                "text": "hello, world!",
+                "quick_reply": {
+                    "payload": "DEVELOPER_DEFINED_PAYLOAD"
+                },
+                "reply_to": {
+                    "mid": "m_1fTq8oLumEyIp3Q2MR-aY7IfLZDamVrALniheU"
+                }
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                sender,
+                timestamp,
+                event: MessagingEvent::Message(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert_eq!(sender.id, Some(1254459154682919));
+                assert_eq!(timestamp.unwrap().timestamp_millis(), 1502905976963);
+                assert_eq!(v.text, Some("hello, world!".to_owned()));
+                assert_eq!(v.quick_reply.unwrap().payload, "DEVELOPER_DEFINED_PAYLOAD");
+                assert!(!v.is_echo);
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "id": "682498171943165"
+            },
+            "recipient": {
+                "id": "1254459154682919"
+            },
+            "timestamp": 1457764197627,
+            "message": {
+                "is_echo": true,
+                "app_id": 1517776481860111,
+                "metadata": "DEVELOPER_DEFINED_METADATA_STRING",
+                "mid": "mid.1457764197618:41d102a3e1ae206a38",
+                "attachments": [
+                    {
+                        "type": "image",
+                        "payload": {
+                            "url": "https://example.com/image.png"
+                        }
+                    }
+                ]
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                event: MessagingEvent::Message(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert!(v.is_echo);
+                assert_eq!(v.app_id, Some(1517776481860111));
+                let attachments = v.attachments.unwrap();
+                assert_eq!(attachments[0].r#type, AttachmentType::Image);
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "682498171943165"
+            },
+            "timestamp": 1527459824000,
+            "postback": {
+                "mid": "m_AG5Hz2Uq7tuwNEhXfYYKj8mJEM_QPpz5jdCK48PnKAjSdjfipqxqMvK8ma6AC8fplwlqLP_5cgXIbu7I3rBN0P",
+                "title": "TITLE_FOR_THE_CTA",
+                "payload": "USER_DEFINED_PAYLOAD"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                timestamp,
+                event: MessagingEvent::Postback(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert_eq!(timestamp, Some(Utc.timestamp_opt(1527459824, 0).unwrap()));
+                assert_eq!(v.payload, Some("USER_DEFINED_PAYLOAD".to_owned()));
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "682498171943165"
+            },
+            "delivery": {
+                "mids": [
+                    "mid.1458668856218:ed81099e15d3f4f233"
+                ],
+                "watermark": 1458668856253
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                timestamp,
+                event: MessagingEvent::Delivery(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert!(timestamp.is_none());
+                assert_eq!(v.watermark.timestamp_millis(), 1458668856253);
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "682498171943165"
+            },
+            "timestamp": 1458668856463,
+            "reaction": {
+                "reaction": "love",
+                "emoji": "❤️",
+                "action": "react",
+                "mid": "mid.1458668856218:ed81099e15d3f4f233"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                event: MessagingEvent::Reaction(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.action, ReactionAction::React);
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "user_ref": "USER_REF"
+            },
+            "recipient": {
+                "id": "682498171943165"
+            },
+            "timestamp": 1234567890,
+            "optin": {
+                "ref": "PASS_THROUGH_PARAM",
+                "user_ref": "USER_REF"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                sender,
+                event: MessagingEvent::Optin(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert_eq!(sender.id, None);
+                assert_eq!(sender.user_ref, Some("USER_REF".to_owned()));
+                assert_eq!(v.r#ref, Some("PASS_THROUGH_PARAM".to_owned()));
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "sender": {
+                "id": "1254459154682919"
+            },
+            "recipient": {
+                "id": "17841405309211844"
+            },
+            "timestamp": 1603059201000,
+            "response_feedback": {"mid": "m_1", "feedback": "Good"}
+        }
+        "#;
+        match serde_json::from_str::<Messaging>(json) {
+            Ok(Messaging {
+                event: MessagingEvent::Unknown(v),
+                ..
+            }) => {
+                println!("{:?}", v);
+
+                assert_eq!(MessagingEvent::Unknown(v).field(), "response_feedback");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let event = MessagingEvent::Unknown(serde_json::json!({
+            "sender": {"id": "1254459154682919"},
+            "recipient": {"id": "17841405309211844"},
+            "timestamp": 1603059201000_u64,
+            "response_feedback": {}
+        }));
+        assert_eq!(event.field(), "response_feedback");
+    }
+}
@@ -1,4 +1,9 @@
 //! [Official doc](https://developers.facebook.com/docs/graph-api/webhooks/reference)
 
 pub mod instagram;
+pub mod instagram_messaging;
+pub mod messaging;
+pub mod messenger;
+pub mod page;
 pub mod permissions;
+pub mod whatsapp;
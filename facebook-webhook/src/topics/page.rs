@@ -0,0 +1,461 @@
+//! [Official doc](https://developers.facebook.com/docs/graph-api/webhooks/reference/page)
+//!
+//! Require [Page Subscribed Apps](https://developers.facebook.com/docs/graph-api/reference/page/subscribed_apps#Creating)
+
+use chrono::{serde::ts_seconds_option, DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+};
+use serde_json::Value;
+
+use crate::serde_ext::{serialize_option_to_string, serialize_to_string};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "field", content = "value", rename_all = "snake_case")]
+pub enum Page {
+    Feed(FeedValue),
+    Mention(MentionValue),
+    Ratings(RatingsValue),
+    Leadgen(LeadgenValue),
+    LiveVideos(LiveVideosValue),
+    Videos(VideosValue),
+    Name(NameValue),
+    Picture(PictureValue),
+    /// Unknown field, or a value this crate can't parse yet.
+    #[serde(untagged)]
+    Unknown {
+        field: String,
+        value: Value,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verb {
+    Add,
+    Block,
+    Edit,
+    Edited,
+    Delete,
+    Follow,
+    Hide,
+    Mute,
+    Remove,
+    Unblock,
+    Unhide,
+    Update,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Author {
+    /// id == User id or Page id
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+    pub name: Option<String>,
+}
+
+//
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "item", rename_all = "snake_case")]
+pub enum FeedValue {
+    Post(FeedPostValue),
+    Comment(FeedCommentValue),
+    Reaction(FeedReactionValue),
+    Share(FeedShareValue),
+    Status(FeedStatusValue),
+    /// Other item, e.g. `photo`, `video`, `like` or `event`, or a value this crate can't parse yet.
+    #[serde(untagged)]
+    Other(Value),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FeedPostValue {
+    /// post_id == `{page_id}_{post_id}`
+    pub post_id: String,
+    pub verb: Verb,
+    pub from: Option<Author>,
+    pub message: Option<String>,
+    pub link: Option<String>,
+    pub photos: Option<Vec<String>>,
+    pub status_type: Option<String>,
+    pub published: Option<u8>,
+    #[serde(default, with = "ts_seconds_option")]
+    pub created_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FeedCommentValue {
+    pub post_id: String,
+    pub comment_id: String,
+    /// parent_id == post_id or comment_id
+    pub parent_id: Option<String>,
+    pub verb: Verb,
+    pub from: Option<Author>,
+    pub message: Option<String>,
+    pub photo: Option<String>,
+    #[serde(default, with = "ts_seconds_option")]
+    pub created_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FeedReactionValue {
+    pub post_id: String,
+    /// Only when reacting to a comment.
+    pub comment_id: Option<String>,
+    pub parent_id: Option<String>,
+    /// like love wow haha sad angry care
+    pub reaction_type: Option<String>,
+    pub verb: Verb,
+    pub from: Option<Author>,
+    #[serde(default, with = "ts_seconds_option")]
+    pub created_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FeedShareValue {
+    pub post_id: String,
+    pub share_id: Option<String>,
+    pub verb: Verb,
+    pub from: Option<Author>,
+    pub message: Option<String>,
+    pub link: Option<String>,
+    pub published: Option<u8>,
+    #[serde(default, with = "ts_seconds_option")]
+    pub created_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FeedStatusValue {
+    pub post_id: String,
+    pub verb: Verb,
+    pub from: Option<Author>,
+    pub message: Option<String>,
+    pub published: Option<u8>,
+    #[serde(default, with = "ts_seconds_option")]
+    pub created_time: Option<DateTime<Utc>>,
+}
+
+//
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MentionValue {
+    pub item: MentionItem,
+    pub post_id: String,
+    /// Only when `item` is `comment`.
+    pub comment_id: Option<String>,
+    pub verb: Verb,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub sender_id: Option<u64>,
+    pub sender_name: Option<String>,
+    pub message: Option<String>,
+    #[serde(default, with = "ts_seconds_option")]
+    pub created_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MentionItem {
+    Post,
+    Comment,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RatingsValue {
+    /// rating or comment
+    pub item: String,
+    pub verb: Verb,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub reviewer_id: Option<u64>,
+    pub reviewer_name: Option<String>,
+    pub rating: Option<u8>,
+    pub review_text: Option<String>,
+    /// positive or negative
+    pub recommendation_type: Option<String>,
+    pub open_graph_story_id: Option<String>,
+    pub comment_id: Option<String>,
+    #[serde(default, with = "ts_seconds_option")]
+    pub created_time: Option<DateTime<Utc>>,
+}
+
+/// [Official doc](https://developers.facebook.com/docs/marketing-api/guides/lead-ads/retrieving#webhooks)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LeadgenValue {
+    /// leadgen_id == [Lead id](https://developers.facebook.com/docs/marketing-api/reference/user-lead-gen-info)
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub leadgen_id: u64,
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub page_id: u64,
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub form_id: u64,
+    /// Missing when the lead is from an organic post.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub ad_id: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_number_from_string",
+        serialize_with = "serialize_option_to_string"
+    )]
+    pub adgroup_id: Option<u64>,
+    #[serde(default, with = "ts_seconds_option")]
+    pub created_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LiveVideosValue {
+    /// id == [Live Video id](https://developers.facebook.com/docs/graph-api/reference/live-video)
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+    /// e.g. live, live_stopped, processing, vod
+    pub status: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct VideosValue {
+    /// id == [Video id](https://developers.facebook.com/docs/graph-api/reference/video)
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+    pub status: VideosStatus,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct VideosStatus {
+    /// e.g. ready, processing, error
+    pub video_status: String,
+}
+
+/// The shape isn't documented, every field is optional.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NameValue {
+    pub verb: Option<Verb>,
+    pub value: Option<String>,
+}
+
+/// The shape isn't documented, every field is optional.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PictureValue {
+    pub verb: Option<Verb>,
+    pub link: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_de() {
+        let json = r#"
+        {
+            "field": "feed",
+            "value": {
+                "item": "status",
+                "post_id": "44444444_444444444",
+                "verb": "add",
+                "published": 1,
+                "created_time": 1520544814,
+                "message": "Example post content.",
+                "from": {
+                    "name": "Test Page",
+                    "id": "1067280970047460"
+                }
+            }
+        }
+        "#;
+        match serde_json::from_str::<Page>(json) {
+            Ok(Page::Feed(FeedValue::Status(v))) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.post_id, "44444444_444444444");
+                assert_eq!(v.verb, Verb::Add);
+                assert_eq!(v.from.unwrap().id, 1067280970047460);
+                assert_eq!(v.created_time.unwrap().timestamp(), 1520544814);
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "feed",
+            "value": {
+                "from": {
+                    "id": "3456789012345678",
+                    "name": "Test User"
+                },
+                "post_id": "44444444_444444444",
+                "comment_id": "444444444_555555555",
+                "parent_id": "44444444_444444444",
+                "created_time": 1520544814,
+                "item": "comment",
+                "verb": "add",
+                "message": "Nice post!"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Page>(json) {
+            Ok(Page::Feed(FeedValue::Comment(v))) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.comment_id, "444444444_555555555");
+                assert_eq!(v.message, Some("Nice post!".to_owned()));
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "feed",
+            "value": {
+                "item": "photo",
+                "post_id": "44444444_444444444",
+                "photo_id": "444444444444444",
+                "verb": "add",
+                "published": 1,
+                "created_time": 1520544814,
+                "link": "https://scontent.xx.fbcdn.net/v/t1.0-9/example.jpg",
+                "from": {
+                    "id": "1067280970047460",
+                    "name": "Test Page"
+                }
+            }
+        }
+        "#;
+        match serde_json::from_str::<Page>(json) {
+            Ok(Page::Feed(FeedValue::Other(v))) => {
+                println!("{:?}", v);
+
+                assert_eq!(v["item"], "photo");
+                assert_eq!(v["photo_id"], "444444444444444");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "feed",
+            "value": {
+                "from": {
+                    "id": "3456789012345678"
+                },
+                "post_id": "44444444_444444444",
+                "created_time": 1520544814,
+                "item": "reaction",
+                "parent_id": "44444444_444444444",
+                "reaction_type": "love",
+                "verb": "add"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Page>(json) {
+            Ok(Page::Feed(FeedValue::Reaction(v))) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.reaction_type, Some("love".to_owned()));
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "mention",
+            "value": {
+                "item": "comment",
+                "post_id": "44444444_444444444",
+                "comment_id": "444444444_555555555",
+                "verb": "add",
+                "sender_id": "3456789012345678",
+                "sender_name": "Test User"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Page>(json) {
+            Ok(Page::Mention(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.item, MentionItem::Comment);
+                assert_eq!(v.sender_id, Some(3456789012345678));
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "leadgen",
+            "value": {
+                "ad_id": "444444444",
+                "form_id": "444444444444",
+                "leadgen_id": "444444444444",
+                "created_time": 1520544814,
+                "page_id": "444444444444",
+                "adgroup_id": "44444444444"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Page>(json) {
+            Ok(Page::Leadgen(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.leadgen_id, 444444444444);
+                assert_eq!(v.ad_id, Some(444444444));
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "videos",
+            "value": {
+                "id": "444444444444",
+                "status": {
+                    "video_status": "ready"
+                }
+            }
+        }
+        "#;
+        match serde_json::from_str::<Page>(json) {
+            Ok(Page::Videos(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.status.video_status, "ready");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
@@ -2,42 +2,54 @@
 //!
 //! Don't require [Page Subscribed Apps](https://developers.facebook.com/docs/graph-api/reference/page/subscribed_apps#Creating)
 
-use serde::{de::Deserializer, Deserialize};
+pub use facebook_permission::FacebookPermission;
 
-#[derive(Deserialize, Debug, Clone)]
+use serde::{de::Deserializer, Deserialize, Serialize, Serializer};
+use serde_json::Value as JsonValue;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(tag = "field", rename_all = "snake_case")]
 pub enum Permissions {
     Connected(FieldFlattenValue),
-    InstagramBasic(FieldValue),
-    InstagramManageComments(FieldValue),
-    InstagramManageInsights(FieldValue),
-    InstagramContentPublish(FieldValue),
-    PagesShowList(FieldValue),
-    PagesManageMetadata(FieldValue),
+    /// Any granted or revoked [permission](https://developers.facebook.com/docs/permissions/reference)
+    #[serde(untagged)]
+    Permission(PermissionFieldValue),
+    /// Unknown field, or a value this crate can't parse yet.
+    #[serde(untagged)]
+    Unknown {
+        field: String,
+        #[serde(default)]
+        value: JsonValue,
+    },
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum Verb {
     Granted,
     Revoked,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct FieldFlattenValue {
     #[serde(flatten)]
     pub value: Value,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct FieldValue {
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PermissionFieldValue {
+    pub field: FacebookPermission,
     pub value: Value,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Value {
     pub verb: Verb,
-    #[serde(default, deserialize_with = "deserialize_target_ids")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_target_ids",
+        serialize_with = "serialize_target_ids"
+    )]
     pub target_ids: Option<Vec<u64>>,
 }
 
@@ -64,6 +76,15 @@ where
     }
 }
 
+fn serialize_target_ids<S>(v: &Option<Vec<u64>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    v.as_ref()
+        .map(|v| v.iter().map(|x| x.to_string()).collect::<Vec<_>>())
+        .serialize(serializer)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,9 +121,10 @@ mod tests {
         }
         "#;
         match serde_json::from_str::<Permissions>(json) {
-            Ok(Permissions::InstagramBasic(v)) => {
+            Ok(Permissions::Permission(v)) => {
                 println!("{:?}", v);
 
+                assert_eq!(v.field, FacebookPermission::InstagramBasic);
                 assert_eq!(v.value.verb, Verb::Granted);
                 assert_eq!(
                     v.value.target_ids,
@@ -112,5 +134,58 @@ mod tests {
             Ok(v) => panic!("{:?}", v),
             Err(err) => panic!("{}", err),
         }
+
+        let json = r#"
+        {
+            "field": "ads_read",
+            "value": {
+                "verb": "revoked"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Permissions>(json) {
+            Ok(Permissions::Permission(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.field, FacebookPermission::AdsRead);
+                assert_eq!(v.value.verb, Verb::Revoked);
+                assert_eq!(v.value.target_ids, None);
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "foo_bar",
+            "value": {
+                "verb": "revoked"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Permissions>(json) {
+            Ok(Permissions::Permission(v)) => {
+                assert_eq!(v.field, FacebookPermission::Other("foo_bar".to_owned()));
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "foo_bar",
+            "value": {
+                "verb": "foo"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Permissions>(json) {
+            Ok(Permissions::Unknown { field, value }) => {
+                assert_eq!(field, "foo_bar");
+                assert_eq!(value["verb"], "foo");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
     }
 }
@@ -0,0 +1,698 @@
+//! [Official doc](https://developers.facebook.com/docs/graph-api/webhooks/reference/whatsapp-business-account)
+//!
+//! Require [Subscribed Apps](https://developers.facebook.com/docs/graph-api/reference/whats-app-business-account/subscribed_apps)
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::deserialize_number_from_string;
+use serde_json::Value;
+
+use crate::serde_ext::{
+    deserialize_option_timestamp, deserialize_timestamp, serialize_option_timestamp,
+    serialize_timestamp, serialize_to_string,
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "field", content = "value", rename_all = "snake_case")]
+pub enum Whatsapp {
+    Messages(MessagesValue),
+    MessageTemplateStatusUpdate(MessageTemplateStatusUpdateValue),
+    PhoneNumberQualityUpdate(PhoneNumberQualityUpdateValue),
+    /// Unknown field, or a value this crate can't parse yet.
+    #[serde(untagged)]
+    Unknown {
+        field: String,
+        value: Value,
+    },
+}
+
+/// [Official doc](https://developers.facebook.com/docs/whatsapp/cloud-api/webhooks/components)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MessagesValue {
+    /// Always `whatsapp`
+    pub messaging_product: String,
+    pub metadata: Metadata,
+    pub contacts: Option<Vec<Contact>>,
+    /// Inbound messages.
+    pub messages: Option<Vec<Message>>,
+    /// Status of outbound messages.
+    pub statuses: Option<Vec<Status>>,
+    pub errors: Option<Vec<Error>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Metadata {
+    pub display_phone_number: String,
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub phone_number_id: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Contact {
+    pub profile: Profile,
+    /// WhatsApp id, the phone number without `+`.
+    pub wa_id: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+}
+
+//
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Message {
+    /// The customer's WhatsApp id.
+    pub from: String,
+    /// id == wamid
+    pub id: String,
+    #[serde(
+        deserialize_with = "deserialize_timestamp",
+        serialize_with = "serialize_timestamp"
+    )]
+    pub timestamp: DateTime<Utc>,
+    pub context: Option<MessageContext>,
+    #[serde(flatten)]
+    pub r#type: MessageType,
+    /// Only when `type` is `unsupported`.
+    pub errors: Option<Vec<Error>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MessageType {
+    Text {
+        text: Text,
+    },
+    Image {
+        image: Media,
+    },
+    Audio {
+        audio: Media,
+    },
+    Video {
+        video: Media,
+    },
+    Document {
+        document: Media,
+    },
+    Sticker {
+        sticker: Media,
+    },
+    Location {
+        location: Location,
+    },
+    Contacts {
+        contacts: Vec<SharedContact>,
+    },
+    Interactive {
+        interactive: Interactive,
+    },
+    Button {
+        button: Button,
+    },
+    Reaction {
+        reaction: Reaction,
+    },
+    /// Other type, e.g. `order`, `system` and `unsupported`, or a value this crate can't parse yet.
+    #[serde(untagged)]
+    Other(Value),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MessageContext {
+    /// Sender of the quoted message.
+    pub from: Option<String>,
+    /// wamid of the quoted message.
+    pub id: Option<String>,
+    pub forwarded: Option<bool>,
+    pub frequently_forwarded: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Text {
+    pub body: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Media {
+    /// id == [Media id](https://developers.facebook.com/docs/whatsapp/cloud-api/reference/media)
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub id: u64,
+    pub mime_type: Option<String>,
+    pub sha256: Option<String>,
+    pub caption: Option<String>,
+    /// Only for `document`.
+    pub filename: Option<String>,
+    /// Only for `audio`, true when it is a voice recording.
+    pub voice: Option<bool>,
+    /// Only for `sticker`.
+    pub animated: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub name: Option<String>,
+    pub address: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Contact card sent by the customer.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SharedContact {
+    pub name: SharedContactName,
+    pub phones: Option<Vec<SharedContactPhone>>,
+    pub emails: Option<Vec<SharedContactEmail>>,
+    pub org: Option<SharedContactOrg>,
+    /// `YYYY-MM-DD`.
+    pub birthday: Option<String>,
+    pub addresses: Option<Vec<Value>>,
+    pub urls: Option<Vec<Value>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SharedContactName {
+    pub formatted_name: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub middle_name: Option<String>,
+    pub suffix: Option<String>,
+    pub prefix: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SharedContactPhone {
+    pub phone: String,
+    /// Only when the phone number is on WhatsApp.
+    pub wa_id: Option<String>,
+    #[serde(rename = "type")]
+    pub r#type: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SharedContactEmail {
+    pub email: String,
+    #[serde(rename = "type")]
+    pub r#type: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SharedContactOrg {
+    pub company: Option<String>,
+    pub department: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Interactive {
+    ButtonReply {
+        button_reply: InteractiveReply,
+    },
+    ListReply {
+        list_reply: InteractiveReply,
+    },
+    /// Other type, e.g. `nfm_reply` of Flows, or a value this crate can't parse yet.
+    #[serde(untagged)]
+    Other(Value),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct InteractiveReply {
+    pub id: String,
+    pub title: String,
+    /// Only for `list_reply`.
+    pub description: Option<String>,
+}
+
+/// Quick reply button of a template message.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Button {
+    pub payload: Option<String>,
+    pub text: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Reaction {
+    /// wamid of the reacted message.
+    pub message_id: String,
+    /// Missing when the reaction is removed.
+    pub emoji: Option<String>,
+}
+
+//
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Status {
+    /// wamid of the outbound message.
+    pub id: String,
+    pub recipient_id: String,
+    pub status: StatusKind,
+    #[serde(
+        deserialize_with = "deserialize_timestamp",
+        serialize_with = "serialize_timestamp"
+    )]
+    pub timestamp: DateTime<Utc>,
+    pub conversation: Option<Conversation>,
+    pub pricing: Option<Pricing>,
+    /// Only when `status` is `failed`.
+    pub errors: Option<Vec<Error>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusKind {
+    Sent,
+    Delivered,
+    Read,
+    Failed,
+    Deleted,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Conversation {
+    pub id: String,
+    /// Only in the first `sent` status of the conversation.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option_timestamp",
+        serialize_with = "serialize_option_timestamp"
+    )]
+    pub expiration_timestamp: Option<DateTime<Utc>>,
+    pub origin: Option<ConversationOrigin>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ConversationOrigin {
+    /// e.g. authentication, marketing, utility, service, referral_conversion
+    #[serde(rename = "type")]
+    pub r#type: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Pricing {
+    pub billable: Option<bool>,
+    /// e.g. CBP
+    pub pricing_model: String,
+    /// e.g. authentication, marketing, utility, service, referral_conversion
+    pub category: String,
+}
+
+/// [Official doc](https://developers.facebook.com/docs/whatsapp/cloud-api/support/error-codes)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Error {
+    pub code: i64,
+    pub title: String,
+    pub message: Option<String>,
+    pub error_data: Option<ErrorData>,
+    pub href: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ErrorData {
+    pub details: String,
+}
+
+//
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MessageTemplateStatusUpdateValue {
+    pub event: MessageTemplateStatusUpdateEvent,
+    #[serde(
+        deserialize_with = "deserialize_number_from_string",
+        serialize_with = "serialize_to_string"
+    )]
+    pub message_template_id: u64,
+    pub message_template_name: String,
+    pub message_template_language: String,
+    /// e.g. NONE, ABUSIVE_CONTENT, INCORRECT_CATEGORY
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MessageTemplateStatusUpdateEvent {
+    Approved,
+    Rejected,
+    Pending,
+    Disabled,
+    Paused,
+    PendingDeletion,
+    Flagged,
+    Reinstated,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PhoneNumberQualityUpdateValue {
+    pub display_phone_number: String,
+    pub event: PhoneNumberQualityUpdateEvent,
+    /// e.g. TIER_50, TIER_250, TIER_1K, TIER_10K, TIER_100K, TIER_UNLIMITED
+    pub current_limit: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PhoneNumberQualityUpdateEvent {
+    Flagged,
+    Unflagged,
+    Downgrade,
+    Upgrade,
+    #[serde(other)]
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_de() {
+        let json = r#"
+        {
+            "field": "messages",
+            "value": {
+                "messaging_product": "whatsapp",
+                "metadata": {
+                    "display_phone_number": "16505551111",
+                    "phone_number_id": "123456123"
+                },
+                "contacts": [
+                    {
+                        "profile": {
+                            "name": "test user name"
+                        },
+                        "wa_id": "16315551181"
+                    }
+                ],
+                "messages": [
+                    {
+                        "from": "16315551181",
+                        "id": "ABGGFlA5Fpa",
+                        "timestamp": "1504902988",
+                        "type": "text",
+                        "text": {
+                            "body": "this is a text message"
+                        }
+                    },
+                    {
+                        "from": "16315551181",
+                        "id": "ABGGFlA5Fpb",
+                        "timestamp": "1504902988",
+                        "type": "image",
+                        "image": {
+                            "caption": "This is a caption",
+                            "mime_type": "image/jpeg",
+                            "sha256": "81d3bd8a8db4868c9520ed47186e8b7c5789e61ff79f7f834be6950b808a90d3",
+                            "id": "2754859441498128"
+                        }
+                    },
+                    {
+                        "from": "16315551181",
+                        "id": "ABGGFlA5Fpc",
+                        "timestamp": "1504902988",
+                        "type": "location",
+                        "location": {
+                            "latitude": 37.483307,
+                            "longitude": 122.148981,
+                            "name": "Main Street Beach"
+                        }
+                    },
+                    {
+                        "from": "16315551181",
+                        "id": "ABGGFlA5Fpd",
+                        "timestamp": "1504902988",
+                        "context": {
+                            "from": "16505551111",
+                            "id": "wamid.gBGGFlaCGg0xcvAdgmZ9plHrf2Mh-o"
+                        },
+                        "type": "interactive",
+                        "interactive": {
+                            "type": "button_reply",
+                            "button_reply": {
+                                "id": "unique-button-identifier-here",
+                                "title": "button-text"
+                            }
+                        }
+                    },
+                    {
+                        "from": "16315551181",
+                        "id": "ABGGFlA5Fpe",
+                        "timestamp": "1504902988",
+                        "type": "reaction",
+                        "reaction": {
+                            "message_id": "wamid.gBGGFlaCGg0xcvAdgmZ9plHrf2Mh-o",
+                            "emoji": "👍"
+                        }
+                    },
+                    {
+                        "from": "16315551181",
+                        "id": "ABGGFlA5Fpf",
+                        "timestamp": "1504902988",
+                        "type": "unsupported",
+                        "errors": [
+                            {
+                                "code": 131051,
+                                "title": "Message type unknown",
+                                "message": "Message type unknown",
+                                "error_data": {
+                                    "details": "Message type is currently not supported."
+                                }
+                            }
+                        ]
+                    },
+                    {
+                        "from": "16315551181",
+                        "id": "ABGGFlA5Fpg",
+                        "timestamp": "1504902988",
+                        "type": "interactive",
+                        "interactive": {
+                            "type": "nfm_reply",
+                            "nfm_reply": {
+                                "response_json": "{\"flow_token\": \"AQAAAAACS5FpgQ_cAAAAAD0QI3s.\"}",
+                                "body": "Sent",
+                                "name": "flow"
+                            }
+                        }
+                    },
+                    {
+                        "from": "16315551181",
+                        "id": "ABGGFlA5Fph",
+                        "timestamp": "1504902988",
+                        "type": "contacts",
+                        "contacts": [
+                            {
+                                "name": {
+                                    "formatted_name": "John Doe",
+                                    "first_name": "John"
+                                },
+                                "phones": [
+                                    {
+                                        "phone": "+1 (631) 555-1182",
+                                        "wa_id": "16315551182",
+                                        "type": "CELL"
+                                    }
+                                ]
+                            }
+                        ]
+                    },
+                    {
+                        "from": "16315551181",
+                        "id": "ABGGFlA5Fpi",
+                        "timestamp": "1504902988",
+                        "type": "order",
+                        "order": {
+                            "catalog_id": "1234",
+                            "product_items": [
+                                {
+                                    "product_retailer_id": "sku-1",
+                                    "quantity": 1,
+                                    "item_price": 9.99,
+                                    "currency": "USD"
+                                }
+                            ]
+                        }
+                    }
+                ]
+            }
+        }
+        "#;
+        match serde_json::from_str::<Whatsapp>(json) {
+            Ok(Whatsapp::Messages(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.metadata.phone_number_id, 123456123);
+                assert_eq!(v.contacts.unwrap()[0].wa_id, "16315551181");
+
+                let messages = v.messages.unwrap();
+                assert_eq!(messages.len(), 9);
+                assert_eq!(messages[0].timestamp.timestamp(), 1504902988);
+                match &messages[0].r#type {
+                    MessageType::Text { text } => assert_eq!(text.body, "this is a text message"),
+                    x => panic!("{:?}", x),
+                }
+                match &messages[1].r#type {
+                    MessageType::Image { image } => assert_eq!(image.id, 2754859441498128),
+                    x => panic!("{:?}", x),
+                }
+                match &messages[2].r#type {
+                    MessageType::Location { location } => {
+                        assert_eq!(location.name, Some("Main Street Beach".to_owned()))
+                    }
+                    x => panic!("{:?}", x),
+                }
+                match &messages[3].r#type {
+                    MessageType::Interactive {
+                        interactive: Interactive::ButtonReply { button_reply },
+                    } => assert_eq!(button_reply.title, "button-text"),
+                    x => panic!("{:?}", x),
+                }
+                assert!(messages[3].context.is_some());
+                match &messages[4].r#type {
+                    MessageType::Reaction { reaction } => {
+                        assert_eq!(reaction.emoji, Some("👍".to_owned()))
+                    }
+                    x => panic!("{:?}", x),
+                }
+                match &messages[5].r#type {
+                    MessageType::Other(v) => {
+                        assert_eq!(v["type"], "unsupported");
+                        assert_eq!(messages[5].errors.as_ref().unwrap()[0].code, 131051)
+                    }
+                    x => panic!("{:?}", x),
+                }
+                match &messages[6].r#type {
+                    MessageType::Interactive {
+                        interactive: Interactive::Other(v),
+                    } => assert_eq!(v["nfm_reply"]["name"], "flow"),
+                    x => panic!("{:?}", x),
+                }
+                match &messages[7].r#type {
+                    MessageType::Contacts { contacts } => {
+                        assert_eq!(contacts[0].name.formatted_name, "John Doe");
+                        assert_eq!(
+                            contacts[0].phones.as_ref().unwrap()[0].wa_id.as_deref(),
+                            Some("16315551182")
+                        );
+                    }
+                    x => panic!("{:?}", x),
+                }
+                match &messages[8].r#type {
+                    MessageType::Other(v) => {
+                        assert_eq!(v["order"]["product_items"][0]["quantity"], 1)
+                    }
+                    x => panic!("{:?}", x),
+                }
+
+                // The payload of the other types survives a round trip.
+                let json = serde_json::to_value(&messages[8]).unwrap();
+                assert_eq!(json["type"], "order");
+                assert_eq!(json["order"]["catalog_id"], "1234");
+                assert_eq!(json["id"], "ABGGFlA5Fpi");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "messages",
+            "value": {
+                "messaging_product": "whatsapp",
+                "metadata": {
+                    "display_phone_number": "16505551111",
+                    "phone_number_id": "123456123"
+                },
+                "statuses": [
+                    {
+                        "id": "wamid.gBGGFlaCGg0xcvAdgmZ9plHrf2Mh-o",
+                        "recipient_id": "16315551181",
+                        "status": "sent",
+                        "timestamp": "1660227234",
+                        "conversation": {
+                            "id": "CONVERSATION_ID",
+                            "expiration_timestamp": "1660313640",
+                            "origin": {
+                                "type": "user_initiated"
+                            }
+                        },
+                        "pricing": {
+                            "billable": true,
+                            "pricing_model": "CBP",
+                            "category": "user_initiated"
+                        }
+                    }
+                ]
+            }
+        }
+        "#;
+        match serde_json::from_str::<Whatsapp>(json) {
+            Ok(Whatsapp::Messages(v)) => {
+                println!("{:?}", v);
+
+                let statuses = v.statuses.unwrap();
+                assert_eq!(statuses[0].status, StatusKind::Sent);
+                let conversation = statuses[0].conversation.as_ref().unwrap();
+                assert_eq!(
+                    conversation.expiration_timestamp.unwrap().timestamp(),
+                    1660313640
+                );
+                assert_eq!(statuses[0].pricing.as_ref().unwrap().pricing_model, "CBP");
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "message_template_status_update",
+            "value": {
+                "event": "APPROVED",
+                "message_template_id": 12345678,
+                "message_template_name": "my_message_template",
+                "message_template_language": "en_US",
+                "reason": "NONE"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Whatsapp>(json) {
+            Ok(Whatsapp::MessageTemplateStatusUpdate(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.event, MessageTemplateStatusUpdateEvent::Approved);
+                assert_eq!(v.message_template_id, 12345678);
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+
+        let json = r#"
+        {
+            "field": "phone_number_quality_update",
+            "value": {
+                "display_phone_number": "16505551111",
+                "event": "FLAGGED",
+                "current_limit": "TIER_10K"
+            }
+        }
+        "#;
+        match serde_json::from_str::<Whatsapp>(json) {
+            Ok(Whatsapp::PhoneNumberQualityUpdate(v)) => {
+                println!("{:?}", v);
+
+                assert_eq!(v.event, PhoneNumberQualityUpdateEvent::Flagged);
+            }
+            Ok(v) => panic!("{:?}", v),
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
@@ -1,9 +1,9 @@
 //! [Official doc](https://developers.facebook.com/docs/graph-api/webhooks/getting-started#verification-requests)
 
 use http::StatusCode;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Query {
     #[serde(rename = "hub.mode")]
     pub mode: String,
@@ -13,6 +13,21 @@ pub struct Query {
     pub verify_token: String,
 }
 
+impl Query {
+    /// What Facebook sends when the callback url is saved.
+    pub fn new(verify_token: impl Into<String>, challenge: i64) -> Self {
+        Self {
+            mode: "subscribe".to_owned(),
+            challenge,
+            verify_token: verify_token.into(),
+        }
+    }
+
+    pub fn to_query_string(&self) -> Result<String, serde_qs::Error> {
+        serde_qs::to_string(self)
+    }
+}
+
 pub fn verify(query_str: &str, verify_token: &str) -> Result<Query, VerifyError> {
     let query: Query = serde_qs::from_str(query_str)?;
 
@@ -115,6 +130,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_query_to_query_string() {
+        let query_str = Query::new("meatyhamhock", 1158201444)
+            .to_query_string()
+            .unwrap();
+        assert_eq!(
+            verify(&query_str, "meatyhamhock").unwrap().challenge,
+            1158201444
+        );
+    }
+
     #[test]
     fn test_pass_back() {
         let res = pass_back(SAMPLE_QUERY_STR, "meatyhamhock");